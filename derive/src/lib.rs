@@ -0,0 +1,137 @@
+//! Derive macros for [`bevy-either`](https://crates.io/crates/bevy-either).
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields};
+
+/// Derives `EnumComponents` for an enum, storing each of its variants as a separate component.
+///
+/// For an enum `Ai` with a variant `Idle`, this generates:
+/// * a component `AiIdle` with the same fields as the variant,
+/// * a read only `either_many!` query `AiRef<'w>` over `&'w AiIdle` and the other variants'
+///   components, and a mutable one `AiMut<'w>` over `&'w mut AiIdle` and the others,
+/// * an `EnumComponents` impl inserting the variant's component while removing the others.
+#[proc_macro_derive(EnumComponents)]
+pub fn derive_enum_components(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Enum(data) if !data.variants.is_empty() => data,
+        Data::Enum(_) => {
+            let message = "EnumComponents needs at least one variant";
+            return Err(Error::new_spanned(&input.ident, message));
+        },
+        _ => {
+            let message = "EnumComponents can only be derived for enums";
+            return Err(Error::new_spanned(&input.ident, message));
+        },
+    };
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(&input.generics, "EnumComponents doesn't support generics"));
+    }
+
+    let vis = &input.vis;
+    let name = &input.ident;
+    let ref_name = format_ident!("{}Ref", name);
+    let mut_name = format_ident!("{}Mut", name);
+    let variants = data.variants.iter().map(|variant| &variant.ident).collect::<Vec<_>>();
+    let components = variants
+        .iter()
+        .map(|variant| format_ident!("{}{}", name, variant))
+        .collect::<Vec<_>>();
+
+    let definitions = data.variants.iter().zip(components.iter()).map(|(variant, component)| {
+        let doc = format!("The component of [`{}::{}`].", name, variant.ident);
+        match &variant.fields {
+            Fields::Named(fields) => {
+                let fields = fields.named.iter().map(|field| {
+                    let (attrs, ident, ty) = (&field.attrs, &field.ident, &field.ty);
+                    quote!(#(#attrs)* #vis #ident: #ty)
+                });
+                quote!(#[doc = #doc] #vis struct #component { #(#fields),* })
+            },
+            Fields::Unnamed(fields) => {
+                let fields = fields.unnamed.iter().map(|field| {
+                    let (attrs, ty) = (&field.attrs, &field.ty);
+                    quote!(#(#attrs)* #vis #ty)
+                });
+                quote!(#[doc = #doc] #vis struct #component(#(#fields),*);)
+            },
+            Fields::Unit => quote!(#[doc = #doc] #vis struct #component;),
+        }
+    });
+
+    let (patterns, constructors): (Vec<_>, Vec<_>) = data.variants
+        .iter()
+        .zip(components.iter())
+        .map(|(variant, component)| {
+            let ident = &variant.ident;
+            match &variant.fields {
+                Fields::Named(fields) => {
+                    let fields = fields.named.iter().map(|field| &field.ident).collect::<Vec<_>>();
+                    (quote!(#name::#ident { #(#fields),* }), quote!(#component { #(#fields),* }))
+                },
+                Fields::Unnamed(fields) => {
+                    let fields = (0..fields.unnamed.len())
+                        .map(|index| format_ident!("field_{}", index))
+                        .collect::<Vec<_>>();
+                    (quote!(#name::#ident(#(#fields),*)), quote!(#component(#(#fields),*)))
+                },
+                Fields::Unit => (quote!(#name::#ident), quote!(#component)),
+            }
+        })
+        .unzip();
+    // the other variants' components are removed together, moving the entity once, in tuples no
+    // longer than Bevy's largest tuple bundle
+    let others = components
+        .iter()
+        .map(|component| components
+            .iter()
+            .filter(|&other| other != component)
+            .collect::<Vec<_>>()
+            .chunks(15)
+            .map(|chunk| quote!((#(#chunk,)*)))
+            .collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    Ok(quote! {
+        #(#definitions)*
+
+        ::bevy_either::either_many!(
+            readonly #ref_name<'w>,
+            #(#variants(&'w #components)),*
+        );
+
+        ::bevy_either::either_many!(
+            #mut_name<'w>,
+            #(#variants(&'w mut #components)),*
+        );
+
+        impl ::bevy_either::EnumComponents for #name {
+            fn insert_variant(self, entity: &mut ::bevy::ecs::world::EntityMut<'_>) {
+                match self {
+                    #(#patterns => {
+                        #(entity.remove_bundle_intersection::<#others>();)*
+                        entity.insert(#constructors);
+                    },)*
+                }
+            }
+
+            fn insert_variant_commands(
+                self,
+                commands: &mut ::bevy::ecs::system::EntityCommands<'_, '_>,
+            ) {
+                match self {
+                    #(#patterns => {
+                        #(commands.remove_bundle::<#others>();)*
+                        commands.insert(#constructors);
+                    },)*
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,70 @@
+use super::*;
+use alloc::vec::Vec;
+
+/// Adds the component access of a branch that is never fetched alongside the branches already in
+/// `access`.
+///
+/// The branch's access is checked only against `base`, the access from before any of the
+/// branches were added. This lets mutually exclusive branches read and write the same
+/// components, e.g. `Either<&mut A, &A>`. `component_count` must be at least the number of
+/// components in the world when the branch's state was initialized.
+///
+/// Only the branch's reads and writes are added, as its filters don't hold for the entities
+/// matched by the other branches. Like with Bevy's own [`Option`] queries, the components read or
+/// written still act as [`With`] filters, as [`FilteredAccess`] can't add access without one.
+#[doc(hidden)]
+pub fn add_exclusive_branch_access(
+    access: &mut FilteredAccess<ComponentId>,
+    base: &FilteredAccess<ComponentId>,
+    component_count: usize,
+    update_branch_access: impl FnOnce(&mut FilteredAccess<ComponentId>),
+) {
+    let mut branch = base.clone();
+    update_branch_access(&mut branch);
+
+    let mut reads_all = Access::default();
+    reads_all.read_all();
+    let mut writes_all = Access::default();
+    for index in 0..component_count {
+        writes_all.add_write(ComponentId::new(index));
+    }
+
+    let writes = branch.access().get_conflicts(&reads_all);
+    for id in branch.access().get_conflicts(&writes_all) {
+        if writes.contains(&id) {
+            access.add_write(id);
+        } else {
+            access.add_read(id);
+        }
+    }
+}
+
+/// Returns the components that a branch requires or excludes, out of the first `component_count`.
+///
+/// [`FilteredAccess`] doesn't expose its filters, so each component is probed by checking the
+/// branch against an access that conflicts with it and only differs by that component's filter.
+#[doc(hidden)]
+pub fn branch_components(
+    component_count: usize,
+    update_branch_access: impl FnOnce(&mut FilteredAccess<ComponentId>),
+) -> Vec<ComponentId> {
+    // no branch can look at the component after the last one, so conflicting on it leaves only
+    // the filters to tell the accesses apart
+    let conflicting = ComponentId::new(component_count);
+    let mut branch = FilteredAccess::default();
+    update_branch_access(&mut branch);
+    branch.add_write(conflicting);
+
+    (0..component_count)
+        .map(ComponentId::new)
+        .filter(|&id| {
+            let mut without = FilteredAccess::default();
+            without.add_write(conflicting);
+            without.add_without(id);
+            let mut with = FilteredAccess::default();
+            with.add_write(conflicting);
+            with.add_with(id);
+            branch.is_compatible(&without) || branch.is_compatible(&with)
+        })
+        .collect()
+}
@@ -0,0 +1,243 @@
+use super::{*, access::add_exclusive_branch_access, shadow::*};
+use core::marker::PhantomData;
+
+/// A [world query](WorldQuery) matching the entities for which at least `K` of the queries in the
+/// tuple `Q` match, like `AtLeast<2, (&Weapon, &Armor, &Shield)>`.
+///
+/// The item is a tuple with an [`Option`] of each query's item, which is [`Some`] for every query
+/// that matches. As the queries can all be fetched together, they can't access the same
+/// components in conflicting ways, unlike with [`Either`]. Unless `K` is the tuple's length, a
+/// query might not match, so only their reads and writes are used to check for conflicts with
+/// other queries, not their filters.
+pub struct AtLeast<const K: usize, Q>(PhantomData<fn() -> Q>);
+
+pub struct AtLeastState<const K: usize, S> {
+    states: S,
+    component_count: usize,
+}
+
+pub struct AtLeastFetch<const K: usize, F> {
+    fetches: F,
+    matches: u32,
+}
+
+macro_rules! impl_at_least {
+    ($($name:ident $index:tt),+) => {
+        unsafe impl<const K: usize, $($name: FetchState),+> FetchState
+            for AtLeastState<K, ($($name,)+)>
+        {
+            fn init(world: &mut World) -> Self {
+                AtLeastState {
+                    states: ($($name::init(world),)+),
+                    component_count: world.components().len(),
+                }
+            }
+
+            fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
+                let count = [$($index),+].len();
+                if K >= count {
+                    // every query has to match, so their filters all hold
+                    $(self.states.$index.update_component_access(access);)+
+                } else {
+                    // the queries are fetched together, so they're still checked against each
+                    // other, but only the union of their reads and writes is added
+                    let base = access.clone();
+                    add_exclusive_branch_access(access, &base, self.component_count, |access| {
+                        $(self.states.$index.update_component_access(access);)+
+                    });
+                }
+            }
+
+            fn update_archetype_component_access(
+                &self,
+                archetype: &Archetype,
+                access: &mut Access<ArchetypeComponentId>,
+            ) {
+                $(self.states.$index.update_archetype_component_access(archetype, access);)+
+            }
+
+            fn matches_archetype(&self, archetype: &Archetype) -> bool {
+                let count = 0 $(+ self.states.$index.matches_archetype(archetype) as usize)+;
+                count >= K
+            }
+
+            fn matches_table(&self, table: &Table) -> bool {
+                let count = 0 $(+ self.states.$index.matches_table(table) as usize)+;
+                count >= K
+            }
+        }
+
+        impl<'w, const K: usize, $($name: Fetch<'w>),+> Fetch<'w> for AtLeastFetch<K, ($($name,)+)> {
+            type Item = ($(Option<$name::Item>,)+);
+            type State = AtLeastState<K, ($($name::State,)+)>;
+
+            fn is_dense(&self) -> bool {
+                $(self.fetches.$index.is_dense())&&+
+            }
+
+            unsafe fn init(
+                world: &World,
+                state: &Self::State,
+                last_change_tick: u32,
+                change_tick: u32,
+            ) -> Self {
+                AtLeastFetch {
+                    fetches: ($($name::init(
+                        world,
+                        &state.states.$index,
+                        last_change_tick,
+                        change_tick,
+                    ),)+),
+                    matches: 0,
+                }
+            }
+
+            unsafe fn set_archetype(
+                &mut self,
+                state: &Self::State,
+                archetype: &Archetype,
+                tables: &Tables,
+            ) {
+                self.matches = 0;
+                $(if state.states.$index.matches_archetype(archetype) {
+                    self.fetches.$index.set_archetype(&state.states.$index, archetype, tables);
+                    self.matches |= 1 << $index;
+                })+
+                if (self.matches.count_ones() as usize) < K {
+                    unmatched(format_args!("Fewer than {} of the queries were matched.", K));
+                }
+            }
+
+            unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
+                self.matches = 0;
+                $(if state.states.$index.matches_table(table) {
+                    self.fetches.$index.set_table(&state.states.$index, table);
+                    self.matches |= 1 << $index;
+                })+
+                if (self.matches.count_ones() as usize) < K {
+                    unmatched(format_args!("Fewer than {} of the queries were matched.", K));
+                }
+            }
+
+            unsafe fn archetype_fetch(&mut self, archetype_index: usize) -> Self::Item {
+                ($(if self.matches & 1 << $index != 0 {
+                    Some(self.fetches.$index.archetype_fetch(archetype_index))
+                } else {
+                    None
+                },)+)
+            }
+
+            unsafe fn table_fetch(&mut self, table_row: usize) -> Self::Item {
+                ($(if self.matches & 1 << $index != 0 {
+                    Some(self.fetches.$index.table_fetch(table_row))
+                } else {
+                    None
+                },)+)
+            }
+        }
+
+        unsafe impl<const K: usize, $($name: ReadOnlyFetch),+> ReadOnlyFetch
+            for AtLeastFetch<K, ($($name,)+)>
+        {}
+
+        impl<const K: usize, $($name: WorldQuery),+> WorldQuery for AtLeast<K, ($($name,)+)> {
+            type Fetch = AtLeastFetch<K, ($($name::Fetch,)+)>;
+            type State = AtLeastState<K, ($($name::State,)+)>;
+        }
+
+        impl<const K: usize, $($name: QueryRequirements),+> QueryRequirements
+            for AtLeast<K, ($($name,)+)>
+        {
+            fn requirements() -> Requirements {
+                let branches = [$($name::requirements()),+];
+                // any K of the queries matching is enough
+                let mut requirements = Requirements::never();
+                for mask in 0u32..1 << branches.len() {
+                    if mask.count_ones() as usize == K {
+                        let mut subset = Requirements::always();
+                        for (index, branch) in branches.iter().enumerate() {
+                            if mask & 1 << index != 0 {
+                                subset = subset.and(branch);
+                            }
+                        }
+                        requirements = requirements.or(&subset);
+                    }
+                }
+                requirements
+            }
+        }
+    };
+}
+
+impl_at_least!(A 0);
+impl_at_least!(A 0, B 1);
+impl_at_least!(A 0, B 1, C 2);
+impl_at_least!(A 0, B 1, C 2, D 3);
+impl_at_least!(A 0, B 1, C 2, D 3, E 4);
+impl_at_least!(A 0, B 1, C 2, D 3, E 4, F 5);
+impl_at_least!(A 0, B 1, C 2, D 3, E 4, F 5, G 6);
+impl_at_least!(A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7);
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    struct Weapon;
+
+    struct Armor;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Shield(u32);
+
+    struct Potion;
+
+    #[test]
+    fn test_at_least() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<Potion>(StorageType::SparseSet)).unwrap();
+        world.spawn().insert(Weapon);
+        let weapon_potion = world.spawn().insert(Weapon).insert(Potion).id();
+        let armor_shield = world.spawn().insert(Armor).insert(Shield(1)).id();
+        let all = world.spawn().insert(Weapon).insert(Armor).insert(Shield(2)).insert(Potion).id();
+        world.spawn().insert(Shield(3));
+
+        let mut query = world.query::<(
+            Entity,
+            AtLeast<2, (With<Weapon>, With<Armor>, &mut Shield, With<Potion>)>,
+        )>();
+        let mut matched = 0;
+        for (entity, (weapon, armor, shield, potion)) in query.iter_mut(&mut world) {
+            if entity == weapon_potion {
+                assert_eq!((weapon, armor, potion), (Some(true), None, Some(true)));
+                assert!(shield.is_none());
+            } else if entity == armor_shield {
+                assert_eq!((weapon, armor, potion), (None, Some(true), None));
+                shield.unwrap().0 += 10;
+            } else {
+                assert_eq!(entity, all);
+                assert_eq!((weapon, armor, potion), (Some(true), Some(true), Some(true)));
+                assert_eq!(shield.as_deref(), Some(&Shield(2)));
+            }
+            matched += 1;
+        }
+        assert_eq!(matched, 3);
+        assert_eq!(world.get::<Shield>(armor_shield), Some(&Shield(11)));
+
+        type Pair = AtLeast<2, (&'static Weapon, &'static Armor, &'static Potion)>;
+        assert!(Requirements::with::<Weapon>().and(&Requirements::with::<Potion>())
+            .implies(&Pair::requirements()));
+        assert!(!Requirements::with::<Weapon>().implies(&Pair::requirements()));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_filters_across_params() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|
+            _: Query<AtLeast<1, (With<Armor>, &mut Shield)>>,
+            _: Query<&mut Shield, Without<Armor>>,
+        | {}).system());
+        update_stage.run(&mut world);
+    }
+}
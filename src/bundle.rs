@@ -0,0 +1,119 @@
+use super::*;
+use bevy::ecs::{bundle::Bundle, system::EntityCommands, world::EntityMut};
+
+/// Insertion of [`Either`] and [`EitherBoth`] bundles into an entity.
+///
+/// A [bundle](Bundle) has the same components for every value of its type, as
+/// [`Bundle::type_info`] doesn't get the value, so neither [`Either<B1, B2>`](Either) nor
+/// [`EitherBoth<B1, B2>`](EitherBoth) can soundly be a bundle. Instead, the bundle(s) they contain
+/// are inserted, letting spawning code keep the shape of the queries.
+pub trait InsertEither {
+    /// Inserts the [left](Either::Left) or [right](Either::Right) bundle.
+    fn insert_either<T: Bundle, U: Bundle>(&mut self, bundle: Either<T, U>) -> &mut Self;
+
+    /// Inserts the [left](EitherBoth::Left), [right](EitherBoth::Right), or
+    /// [both](EitherBoth::Both) bundles. When inserting both, the left bundle is inserted first.
+    fn insert_either_both<T: Bundle, U: Bundle>(&mut self, bundle: EitherBoth<T, U>) -> &mut Self;
+
+    /// Inserts the component of `value`'s variant, removing the components of its other variants.
+    fn insert_enum<E: EnumComponents>(&mut self, value: E) -> &mut Self;
+}
+
+/// An enum whose variants are stored as separate components, usually implemented with
+/// [`#[derive(EnumComponents)]`](macro@crate::EnumComponents).
+pub trait EnumComponents {
+    /// Inserts the component of this value's variant, removing the components of the other
+    /// variants.
+    fn insert_variant(self, entity: &mut EntityMut<'_>);
+
+    /// Like [`insert_variant`](EnumComponents::insert_variant), but through [`Commands`].
+    fn insert_variant_commands(self, commands: &mut EntityCommands<'_, '_>);
+}
+
+impl InsertEither for EntityMut<'_> {
+    fn insert_either<T: Bundle, U: Bundle>(&mut self, bundle: Either<T, U>) -> &mut Self {
+        match bundle {
+            Either::Left(l) => self.insert_bundle(l),
+            Either::Right(r) => self.insert_bundle(r),
+        }
+    }
+
+    fn insert_either_both<T: Bundle, U: Bundle>(&mut self, bundle: EitherBoth<T, U>) -> &mut Self {
+        match bundle {
+            EitherBoth::Left(l) => self.insert_bundle(l),
+            EitherBoth::Right(r) => self.insert_bundle(r),
+            EitherBoth::Both(l, r) => self.insert_bundle(l).insert_bundle(r),
+        }
+    }
+
+    fn insert_enum<E: EnumComponents>(&mut self, value: E) -> &mut Self {
+        value.insert_variant(self);
+        self
+    }
+}
+
+impl InsertEither for EntityCommands<'_, '_> {
+    fn insert_either<T: Bundle, U: Bundle>(&mut self, bundle: Either<T, U>) -> &mut Self {
+        match bundle {
+            Either::Left(l) => self.insert_bundle(l),
+            Either::Right(r) => self.insert_bundle(r),
+        }
+    }
+
+    fn insert_either_both<T: Bundle, U: Bundle>(&mut self, bundle: EitherBoth<T, U>) -> &mut Self {
+        match bundle {
+            EitherBoth::Left(l) => self.insert_bundle(l),
+            EitherBoth::Right(r) => self.insert_bundle(r),
+            EitherBoth::Both(l, r) => self.insert_bundle(l).insert_bundle(r),
+        }
+    }
+
+    fn insert_enum<E: EnumComponents>(&mut self, value: E) -> &mut Self {
+        value.insert_variant_commands(self);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    type PlayerBundle = (u8, u32);
+
+    type NpcBundle = (i8, u32);
+
+    #[test]
+    fn test_insert_either() {
+        let mut world = World::default();
+        let player = world
+            .spawn()
+            .insert_either(Either::<PlayerBundle, NpcBundle>::Left((1, 2)))
+            .id();
+        let npc = world
+            .spawn()
+            .insert_either(Either::<PlayerBundle, NpcBundle>::Right((3, 4)))
+            .id();
+        assert_eq!(world.get::<u8>(player), Some(&1));
+        assert_eq!(world.get::<i8>(player), None);
+        assert_eq!(world.get::<u32>(player), Some(&2));
+        assert_eq!(world.get::<u8>(npc), None);
+        assert_eq!(world.get::<i8>(npc), Some(&3));
+        assert_eq!(world.get::<u32>(npc), Some(&4));
+    }
+
+    #[test]
+    fn test_insert_either_both() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|mut commands: Commands| {
+            commands
+                .spawn()
+                .insert_either_both(EitherBoth::Both((1u8,), (2i8, 3u32)));
+        }).system());
+        update_stage.run(&mut world);
+        let mut query = world.query::<(&u8, &i8, &u32)>();
+        let mut iter = query.iter(&world);
+        assert_eq!(iter.next(), Some((&1, &2, &3)));
+        assert_eq!(iter.next(), None);
+    }
+}
@@ -0,0 +1,223 @@
+use super::*;
+use alloc::vec::Vec;
+use bevy::ecs::world::{FromWorld, WorldId};
+use core::{iter::Enumerate, slice};
+
+/// Like a [`QueryState<Q, F>`](QueryState), but iterating over the entities grouped by the
+/// variant of `Q` they're given, in declaration order, like every [`Left`](Either::Left) item
+/// before every [`Right`](Either::Right) item for [`Either`].
+///
+/// The items aren't collected or sorted: the matched archetypes are kept per variant, and walked
+/// one variant after another.
+pub struct VariantQueryState<Q: VariantQuery, F: WorldQuery = ()> {
+    world_id: WorldId,
+    variants: VariantArchetypes<Q>,
+    filter_state: F::State,
+}
+
+impl<Q: VariantQuery, F: WorldQuery> VariantQueryState<Q, F>
+where
+    F::Fetch: FilterFetch,
+{
+    pub fn new(world: &mut World) -> Self {
+        let variants = VariantArchetypes::<Q>::from_world(world);
+        let filter_state = F::State::init(world);
+        // panics like `QueryState::new` if the query's accesses conflict
+        let mut component_access = FilteredAccess::default();
+        variants.state().update_component_access(&mut component_access);
+        filter_state.update_component_access(&mut component_access);
+        let mut state = VariantQueryState {
+            world_id: world.id(),
+            variants,
+            filter_state,
+        };
+        state.validate_world_and_update_archetypes(world);
+        state
+    }
+
+    pub fn validate_world_and_update_archetypes(&mut self, world: &World) {
+        if world.id() != self.world_id {
+            panic!(
+                "Attempted to use {} with a mismatched World. VariantQueryStates can only be used \
+                with the World they were created from.",
+                core::any::type_name::<Self>(),
+            );
+        }
+        self.variants.update(world.archetypes());
+    }
+
+    /// Iterates over the items grouped by variant, in declaration order.
+    pub fn iter_by_variant<'w, 's>(&'s mut self, world: &'w World) -> VariantIter<'w, 's, Q, F>
+    where
+        Q::Fetch: ReadOnlyFetch,
+    {
+        // SAFE: query is read only
+        unsafe { self.iter_by_variant_unchecked(world) }
+    }
+
+    /// Mutably iterates over the items grouped by variant, in declaration order.
+    pub fn iter_by_variant_mut<'w, 's>(
+        &'s mut self,
+        world: &'w mut World,
+    ) -> VariantIter<'w, 's, Q, F> {
+        // SAFE: query has unique world access
+        unsafe { self.iter_by_variant_unchecked(world) }
+    }
+
+    /// # Safety
+    ///
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    pub unsafe fn iter_by_variant_unchecked<'w, 's>(
+        &'s mut self,
+        world: &'w World,
+    ) -> VariantIter<'w, 's, Q, F> {
+        self.validate_world_and_update_archetypes(world);
+        let (last_change_tick, change_tick) = (world.last_change_tick(), world.read_change_tick());
+        VariantIter {
+            archetypes: world.archetypes(),
+            tables: &world.storages().tables,
+            fetch: Q::Fetch::init(world, self.variants.state(), last_change_tick, change_tick),
+            filter: F::Fetch::init(world, &self.filter_state, last_change_tick, change_tick),
+            variants: self.variants.variants().iter().enumerate(),
+            variant: 0,
+            archetype_ids: [].iter(),
+            state: self,
+            current_len: 0,
+            current_index: 0,
+        }
+    }
+}
+
+/// An [`Iterator`] over the items of a [`VariantQueryState`], grouped by variant.
+pub struct VariantIter<'w, 's, Q: VariantQuery, F: WorldQuery> {
+    archetypes: &'w Archetypes,
+    tables: &'w Tables,
+    state: &'s VariantQueryState<Q, F>,
+    variants: Enumerate<slice::Iter<'s, Vec<ArchetypeId>>>,
+    variant: usize,
+    archetype_ids: slice::Iter<'s, ArchetypeId>,
+    fetch: Q::Fetch,
+    filter: F::Fetch,
+    current_len: usize,
+    current_index: usize,
+}
+
+impl<'w, 's, Q: VariantQuery, F: WorldQuery> Iterator for VariantIter<'w, 's, Q, F>
+where
+    Q::Fetch: VariantFetch<'w>,
+    F::Fetch: FilterFetch,
+{
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        unsafe {
+            loop {
+                if self.current_index == self.current_len {
+                    let archetype_id = match self.archetype_ids.next() {
+                        Some(&archetype_id) => archetype_id,
+                        None => {
+                            let (variant, archetype_ids) = self.variants.next()?;
+                            self.variant = variant;
+                            self.archetype_ids = archetype_ids.iter();
+                            continue;
+                        }
+                    };
+                    let archetype = &self.archetypes[archetype_id];
+                    if !self.state.filter_state.matches_archetype(archetype) {
+                        continue;
+                    }
+                    // the archetype was already resolved when it was sorted into its variant
+                    self.fetch.set_archetype_variant(
+                        self.state.variants.state(),
+                        self.variant,
+                        archetype,
+                        self.tables,
+                    );
+                    self.filter.set_archetype(&self.state.filter_state, archetype, self.tables);
+                    self.current_len = archetype.len();
+                    self.current_index = 0;
+                    continue;
+                }
+
+                if !self.filter.archetype_filter_fetch(self.current_index) {
+                    self.current_index += 1;
+                    continue;
+                }
+
+                let item = self.fetch.archetype_fetch(self.current_index);
+                self.current_index += 1;
+                return Some(item);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use alloc::vec;
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct A(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct B(u32);
+
+    struct Skip;
+
+    #[test]
+    fn test_iter_by_variant() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet)).unwrap();
+        world.spawn().insert(B(1));
+        world.spawn().insert(A(2));
+        world.spawn().insert(B(3)).insert(1u8);
+        world.spawn().insert(A(4)).insert(B(5));
+        world.spawn().insert(A(6)).insert(Skip);
+
+        let mut state = VariantQueryState::<Either<&A, &B>, Without<Skip>>::new(&mut world);
+        let items: Vec<_> = state.iter_by_variant(&world).collect();
+        assert_eq!(items, vec![
+            Either::Left(&A(2)),
+            Either::Left(&A(4)),
+            Either::Right(&B(1)),
+            Either::Right(&B(3)),
+        ]);
+
+        let mut state = VariantQueryState::<EitherBoth<&A, &B>, Without<Skip>>::new(&mut world);
+        let items: Vec<_> = state.iter_by_variant(&world).collect();
+        assert_eq!(items, vec![
+            EitherBoth::Left(&A(2)),
+            EitherBoth::Right(&B(1)),
+            EitherBoth::Right(&B(3)),
+            EitherBoth::Both(&A(4), &B(5)),
+        ]);
+
+        world.spawn().insert(B(7)).insert(2u16);
+        let mut state = VariantQueryState::<Either<&mut A, &mut B>>::new(&mut world);
+        for item in state.iter_by_variant_mut(&mut world) {
+            match item {
+                Either::Left(mut a) => a.0 += 10,
+                Either::Right(mut b) => b.0 += 10,
+            }
+        }
+        let mut query = world.query::<Either<&A, &B>>();
+        let mut sum = 0;
+        for item in query.iter(&world) {
+            sum += match item {
+                Either::Left(a) => a.0,
+                Either::Right(b) => b.0,
+            };
+        }
+        assert_eq!(sum, 2 + 4 + 6 + 1 + 3 + 7 + 60);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exclusive_access() {
+        let mut world = World::default();
+        VariantQueryState::<Either<(&A, &mut A), &B>>::new(&mut world);
+    }
+}
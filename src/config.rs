@@ -0,0 +1,599 @@
+use super::*;
+use core::{
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+use alloc::{sync::Arc, vec::Vec};
+use bevy::ecs::{
+    query::FilterFetch,
+    system::{OptionResState, SystemParam, SystemParamFetch, SystemParamState, SystemState},
+};
+
+/// The [resource](World::insert_resource) configuring the variants of [`Configured<Q>`], giving
+/// the enabled variants in priority order.
+///
+/// Variants are numbered in declaration order from `0`, e.g. `Left` is `0` and `Right` is `1` for
+/// [`Either`]. Variants missing from [`order`](VariantConfig::order) are disabled. Without this
+/// resource, every variant is enabled in declaration order.
+///
+/// A [`ConfiguredQuery<Q, F>`](ConfiguredQuery) reads the resource each time its system runs, so
+/// changes take effect on the next run. Queries created otherwise, like with [`World::query`],
+/// keep the configuration from when they were created.
+pub struct VariantConfig<Q> {
+    pub order: Vec<usize>,
+    marker: PhantomData<fn() -> Q>,
+}
+
+impl<Q> VariantConfig<Q> {
+    /// Creates a configuration with the given variants enabled, from highest to lowest priority.
+    pub fn new(order: Vec<usize>) -> Self {
+        VariantConfig {
+            order,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns whether `variant` is enabled.
+    pub fn is_enabled(&self, variant: usize) -> bool {
+        self.order.contains(&variant)
+    }
+
+    /// Enables `variant` with the lowest priority, if it isn't enabled already.
+    pub fn enable(&mut self, variant: usize) {
+        if !self.is_enabled(variant) {
+            self.order.push(variant);
+        }
+    }
+
+    /// Disables `variant`.
+    pub fn disable(&mut self, variant: usize) {
+        self.order.retain(|&v| v != variant);
+    }
+}
+
+/// A [fetcher](Fetch) giving one of many variants, which can be matched and set individually.
+///
+/// # Safety
+///
+/// `set_archetype_variant` and `set_table_variant` must make the fetcher give `variant`'s item,
+/// and must only be called when `variant` matches.
+pub unsafe trait VariantFetch<'w>: Fetch<'w> {
+    /// The number of variants.
+    const VARIANT_COUNT: usize;
+
+    fn matches_archetype_variant(
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+    ) -> bool;
+
+    fn matches_table_variant(state: &Self::State, variant: usize, table: &Table) -> bool;
+
+    /// # Safety
+    ///
+    /// Same as [`Fetch::set_archetype`], and `variant` must match the archetype.
+    unsafe fn set_archetype_variant(
+        &mut self,
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+        tables: &Tables,
+    );
+
+    /// # Safety
+    ///
+    /// Same as [`Fetch::set_table`], and `variant` must match the table.
+    unsafe fn set_table_variant(&mut self, state: &Self::State, variant: usize, table: &Table);
+}
+
+/// The variant order of a [`VariantConfig<Q>`], copied out of the resource so that the fetchers
+/// never read it while a [`ResMut`] could be writing it. It's only updated by the
+/// [`ConfiguredQuery`] owning the query, which is atomic so that it can be shared with the fetch
+/// states.
+struct VariantOrder {
+    configured: AtomicBool,
+    len: AtomicUsize,
+    order: Vec<AtomicUsize>,
+}
+
+/// Hands a [`ConfiguredQuery`]'s order to the fetch states created along with it.
+struct PendingOrder<Q>(Arc<VariantOrder>, PhantomData<fn() -> Q>);
+
+impl VariantOrder {
+    /// Returns the order handed over by a [`ConfiguredQuery`] being created, or a new one, updated
+    /// from the current configuration.
+    fn init<Q: 'static>(world: &World, variant_count: usize) -> Arc<Self> {
+        let order = world.get_resource::<PendingOrder<Q>>().map_or_else(
+            || Arc::new(VariantOrder {
+                configured: AtomicBool::new(false),
+                len: AtomicUsize::new(0),
+                order: (0..variant_count).map(|_| AtomicUsize::new(0)).collect(),
+            }),
+            |pending| pending.0.clone(),
+        );
+        order.store(world.get_resource::<VariantConfig<Q>>());
+        order
+    }
+
+    fn store<Q>(&self, config: Option<&VariantConfig<Q>>) {
+        let order = config.map_or(&[][..], |config| &config.order[..]);
+        let mut len = 0;
+        for &variant in order {
+            if variant < self.order.len() && !self.enabled().take(len).any(|v| v == variant) {
+                self.order[len].store(variant, Ordering::Relaxed);
+                len += 1;
+            }
+        }
+        self.len.store(len, Ordering::Relaxed);
+        self.configured.store(config.is_some(), Ordering::Relaxed);
+    }
+
+    fn enabled(&self) -> impl Iterator<Item = usize> + '_ {
+        self.order.iter().map(|variant| variant.load(Ordering::Relaxed))
+    }
+
+    /// Returns every variant in the order they are tried, along with whether it's enabled.
+    /// Disabled variants come last, in declaration order.
+    fn variants(&self) -> impl Iterator<Item = (usize, bool)> + '_ {
+        let len = self.len.load(Ordering::Relaxed);
+        let configured = self.configured.load(Ordering::Relaxed);
+        self.enabled()
+            .take(len)
+            .map(|variant| (variant, true))
+            .chain((0..self.order.len())
+                .filter(move |&variant| !self.enabled().take(len).any(|v| v == variant))
+                .map(move |variant| (variant, !configured)))
+    }
+}
+
+/// A [world query](WorldQuery) over the variants of `Q`, like [`Either`] or an
+/// [`either_many!`](either_many) type, with their priority and enabled-ness taken from the
+/// [`VariantConfig<Q>`] resource at runtime.
+///
+/// The archetypes matched by `Q` don't depend on the configuration, so an entity only matching
+/// disabled variants is still given, as its highest priority disabled variant. To skip these
+/// entities, filter the query with [`WithEnabledVariant<Q>`].
+///
+/// The configuration is copied when the query is created, so the fetchers never read the resource.
+/// In systems, use a [`ConfiguredQuery<Q, F>`](ConfiguredQuery) to follow changes to it.
+pub struct Configured<Q>(PhantomData<fn() -> Q>);
+
+pub struct ConfiguredState<Q: WorldQuery> {
+    state: Q::State,
+    order: Arc<VariantOrder>,
+}
+
+unsafe impl<Q> FetchState for ConfiguredState<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+{
+    fn init(world: &mut World) -> Self {
+        ConfiguredState {
+            state: Q::State::init(world),
+            order: VariantOrder::init::<Q>(world, <Q::Fetch as VariantFetch>::VARIANT_COUNT),
+        }
+    }
+
+    fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
+        self.state.update_component_access(access);
+    }
+
+    fn update_archetype_component_access(
+        &self,
+        archetype: &Archetype,
+        access: &mut Access<ArchetypeComponentId>,
+    ) {
+        self.state.update_archetype_component_access(archetype, access);
+    }
+
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.state.matches_archetype(archetype)
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        self.state.matches_table(table)
+    }
+}
+
+pub struct ConfiguredFetch<Q: WorldQuery> {
+    fetch: Q::Fetch,
+}
+
+impl<'w, Q> Fetch<'w> for ConfiguredFetch<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'x> VariantFetch<'x>,
+{
+    type Item = <Q::Fetch as Fetch<'w>>::Item;
+    type State = ConfiguredState<Q>;
+
+    fn is_dense(&self) -> bool {
+        self.fetch.is_dense()
+    }
+
+    unsafe fn init(
+        world: &World,
+        state: &Self::State,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self {
+        ConfiguredFetch {
+            fetch: Q::Fetch::init(world, &state.state, last_change_tick, change_tick),
+        }
+    }
+
+    unsafe fn set_archetype(
+        &mut self,
+        state: &Self::State,
+        archetype: &Archetype,
+        tables: &Tables,
+    ) {
+        let variant = state.order
+            .variants()
+            .map(|(variant, _)| variant)
+            .find(|&variant| Q::Fetch::matches_archetype_variant(&state.state, variant, archetype));
+        if let Some(variant) = variant {
+            self.fetch.set_archetype_variant(&state.state, variant, archetype, tables);
+        } else {
+            unmatched(format_args!("None of the variants were matched. At least one should be."));
+        }
+    }
+
+    unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
+        let variant = state.order
+            .variants()
+            .map(|(variant, _)| variant)
+            .find(|&variant| Q::Fetch::matches_table_variant(&state.state, variant, table));
+        if let Some(variant) = variant {
+            self.fetch.set_table_variant(&state.state, variant, table);
+        } else {
+            unmatched(format_args!("None of the variants were matched. At least one should be."));
+        }
+    }
+
+    unsafe fn archetype_fetch(&mut self, archetype_index: usize) -> Self::Item {
+        self.fetch.archetype_fetch(archetype_index)
+    }
+
+    unsafe fn table_fetch(&mut self, table_row: usize) -> Self::Item {
+        self.fetch.table_fetch(table_row)
+    }
+}
+
+unsafe impl<Q: WorldQuery> ReadOnlyFetch for ConfiguredFetch<Q> where Q::Fetch: ReadOnlyFetch {}
+
+impl<Q> WorldQuery for Configured<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+{
+    type Fetch = ConfiguredFetch<Q>;
+    type State = ConfiguredState<Q>;
+}
+
+/// A [filter](bevy::ecs::query::FilterFetch) matching the entities for which an enabled variant
+/// of `Q` matches, as configured by [`VariantConfig<Q>`].
+///
+/// Like [`Configured<Q>`], this copies the configuration when the query is created, or follows it
+/// as part of a [`ConfiguredQuery<Q, F>`](ConfiguredQuery).
+pub struct WithEnabledVariant<Q>(PhantomData<fn() -> Q>);
+
+pub struct WithEnabledVariantState<Q: WorldQuery> {
+    state: Q::State,
+    order: Arc<VariantOrder>,
+}
+
+unsafe impl<Q> FetchState for WithEnabledVariantState<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+{
+    fn init(world: &mut World) -> Self {
+        WithEnabledVariantState {
+            state: Q::State::init(world),
+            order: VariantOrder::init::<Q>(world, <Q::Fetch as VariantFetch>::VARIANT_COUNT),
+        }
+    }
+
+    // nothing is fetched from `Q`
+    fn update_component_access(&self, _access: &mut FilteredAccess<ComponentId>) {}
+
+    fn update_archetype_component_access(
+        &self,
+        _archetype: &Archetype,
+        _access: &mut Access<ArchetypeComponentId>,
+    ) {
+    }
+
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.state.matches_archetype(archetype)
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        self.state.matches_table(table)
+    }
+}
+
+pub struct WithEnabledVariantFetch<Q> {
+    dense: bool,
+    matches: bool,
+    marker: PhantomData<fn() -> Q>,
+}
+
+impl<'w, Q> Fetch<'w> for WithEnabledVariantFetch<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'x> VariantFetch<'x>,
+{
+    type Item = bool;
+    type State = WithEnabledVariantState<Q>;
+
+    fn is_dense(&self) -> bool {
+        // a table can't tell the archetypes with `Q`'s sparse components apart
+        self.dense
+    }
+
+    unsafe fn init(
+        world: &World,
+        state: &Self::State,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self {
+        WithEnabledVariantFetch {
+            dense: Q::Fetch::init(world, &state.state, last_change_tick, change_tick).is_dense(),
+            matches: false,
+            marker: PhantomData,
+        }
+    }
+
+    unsafe fn set_archetype(
+        &mut self,
+        state: &Self::State,
+        archetype: &Archetype,
+        _tables: &Tables,
+    ) {
+        self.matches = state.order
+            .variants()
+            .take_while(|&(_, enabled)| enabled)
+            .any(|(variant, _)| {
+                Q::Fetch::matches_archetype_variant(&state.state, variant, archetype)
+            });
+    }
+
+    unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
+        self.matches = state.order
+            .variants()
+            .take_while(|&(_, enabled)| enabled)
+            .any(|(variant, _)| Q::Fetch::matches_table_variant(&state.state, variant, table));
+    }
+
+    unsafe fn archetype_fetch(&mut self, _archetype_index: usize) -> bool {
+        self.matches
+    }
+
+    unsafe fn table_fetch(&mut self, _table_row: usize) -> bool {
+        self.matches
+    }
+}
+
+unsafe impl<Q> ReadOnlyFetch for WithEnabledVariantFetch<Q> {}
+
+impl<Q> WorldQuery for WithEnabledVariant<Q>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+{
+    type Fetch = WithEnabledVariantFetch<Q>;
+    type State = WithEnabledVariantState<Q>;
+}
+
+/// A [system parameter](SystemParam) for a [`Query<Configured<Q>, F>`](Configured) that reads the
+/// [`VariantConfig<Q>`] resource each time the system runs, so that changes to it take effect.
+///
+/// The resource is read like a [`Res<VariantConfig<Q>>`](Res), so a
+/// [`ResMut<VariantConfig<Q>>`](ResMut) in the same system panics, and systems writing it don't run
+/// at the same time. The query is given by dereferencing, like `query.iter()`.
+pub struct ConfiguredQuery<'a, Q, F = ()>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    query: Query<'a, Configured<Q>, F>,
+}
+
+impl<'a, Q, F> Deref for ConfiguredQuery<'a, Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    type Target = Query<'a, Configured<Q>, F>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.query
+    }
+}
+
+impl<'a, Q, F> DerefMut for ConfiguredQuery<'a, Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.query
+    }
+}
+
+pub struct ConfiguredQueryState<Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    config: OptionResState<VariantConfig<Q>>,
+    order: Arc<VariantOrder>,
+    query: QueryState<Configured<Q>, F>,
+}
+
+// SAFE: the read of the resource is registered by `OptionResState`, and the query's access by
+// `QueryState`.
+unsafe impl<Q, F> SystemParamState for ConfiguredQueryState<Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    type Config = ();
+
+    fn init(world: &mut World, system_state: &mut SystemState, _config: Self::Config) -> Self {
+        let config = OptionResState::init(world, system_state, ());
+        let order = VariantOrder::init::<Q>(world, <Q::Fetch as VariantFetch>::VARIANT_COUNT);
+        // the fetch states of `Configured<Q>` and `WithEnabledVariant<Q>` share `order`, so that
+        // only this state updates it
+        world.insert_resource(PendingOrder::<Q>(order.clone(), PhantomData));
+        let query = QueryState::init(world, system_state, ());
+        world.remove_resource::<PendingOrder<Q>>();
+        ConfiguredQueryState { config, order, query }
+    }
+
+    fn new_archetype(&mut self, archetype: &Archetype, system_state: &mut SystemState) {
+        SystemParamState::new_archetype(&mut self.query, archetype, system_state);
+    }
+
+    fn default_config() {}
+}
+
+impl<'a, Q, F> SystemParam for ConfiguredQuery<'a, Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    type Fetch = ConfiguredQueryState<Q, F>;
+}
+
+impl<'a, Q, F> SystemParamFetch<'a> for ConfiguredQueryState<Q, F>
+where
+    Q: WorldQuery + 'static,
+    Q::Fetch: for<'w> VariantFetch<'w>,
+    F: WorldQuery + 'static,
+    F::Fetch: FilterFetch,
+{
+    type Item = ConfiguredQuery<'a, Q, F>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'a mut Self,
+        system_state: &'a SystemState,
+        world: &'a World,
+        change_tick: u32,
+    ) -> Self::Item {
+        let ConfiguredQueryState { config, order, query } = state;
+        // the fetch states sharing `order` are only reachable through `query`, which isn't in use
+        let config = OptionResState::get_param(config, system_state, world, change_tick);
+        order.store(config.as_deref());
+        ConfiguredQuery {
+            query: QueryState::get_param(query, system_state, world, change_tick),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use alloc::vec;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    fn count(world: &mut World) -> (usize, usize, usize) {
+        let mut query = world.query::<Configured<Either<&A, &B>>>();
+        let (left, right) = query.iter(world).fold((0, 0), |(left, right), item| match item {
+            Either::Left(_) => (left + 1, right),
+            Either::Right(_) => (left, right + 1),
+        });
+        let mut filtered = world
+            .query_filtered::<Configured<Either<&A, &B>>, WithEnabledVariant<Either<&A, &B>>>();
+        let mut entities = world.query_filtered::<Entity, WithEnabledVariant<Either<&A, &B>>>();
+        assert_eq!(filtered.iter(world).count(), entities.iter(world).count());
+        (left, right, filtered.iter(world).count())
+    }
+
+    #[test]
+    fn test_variant_config() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet)).unwrap();
+        world.spawn().insert(A);
+        world.spawn().insert(A).insert(B);
+        world.spawn().insert(B);
+        world.spawn().insert(B);
+
+        assert_eq!(count(&mut world), (2, 2, 4));
+        world.insert_resource(VariantConfig::<Either<&A, &B>>::new(vec![1, 0]));
+        assert_eq!(count(&mut world), (1, 3, 4));
+        world.get_resource_mut::<VariantConfig<Either<&A, &B>>>().unwrap().disable(0);
+        assert_eq!(count(&mut world), (1, 3, 3));
+        world.get_resource_mut::<VariantConfig<Either<&A, &B>>>().unwrap().disable(1);
+        assert_eq!(count(&mut world), (2, 2, 0));
+    }
+
+    #[test]
+    fn test_configured_query() {
+        struct Counts(usize, usize);
+
+        fn count(query: ConfiguredQuery<Either<&A, &B>>, mut counts: ResMut<Counts>) {
+            for item in query.iter() {
+                match item {
+                    Either::Left(_) => counts.0 += 1,
+                    Either::Right(_) => counts.1 += 1,
+                }
+            }
+        }
+
+        let mut world = World::default();
+        world.spawn().insert(A).insert(B);
+        world.insert_resource(Counts(0, 0));
+        let mut update_stage = SystemStage::single(count.system());
+        update_stage.run(&mut world);
+        world.insert_resource(VariantConfig::<Either<&A, &B>>::new(vec![1]));
+        update_stage.run(&mut world);
+        let counts = world.get_resource::<Counts>().unwrap();
+        assert_eq!((counts.0, counts.1), (1, 1));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_config_access() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|
+            _: ConfiguredQuery<Either<&A, &B>>,
+            _: ResMut<VariantConfig<Either<&A, &B>>>,
+        | {}).system());
+        world.insert_resource(VariantConfig::<Either<&A, &B>>::new(vec![0, 1]));
+        update_stage.run(&mut world);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_config_access_after_res_mut() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|
+            _: ResMut<VariantConfig<Either<&A, &B>>>,
+            _: ConfiguredQuery<Either<&A, &B>>,
+        | {}).system());
+        world.insert_resource(VariantConfig::<Either<&A, &B>>::new(vec![0, 1]));
+        update_stage.run(&mut world);
+    }
+}
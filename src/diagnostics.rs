@@ -0,0 +1,160 @@
+use super::*;
+use alloc::{boxed::Box, format, vec, vec::Vec};
+use bevy::app::{AppBuilder, Plugin};
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics};
+
+/// A [plugin](Plugin) recording, every frame, how many entities each variant of some
+/// [variant queries](VariantQuery) is given, and how many archetypes they match, into the
+/// [`Diagnostics`] resource.
+///
+/// Each query is registered under a name with [`with`](BevyEitherDiagnosticsPlugin::with), which
+/// prefixes its diagnostics, like `ai/Idle`, `ai/Chase` and `ai/archetypes`. The counts come from
+/// the archetypes alone, so they cost a pass over the archetypes rather than the entities.
+#[derive(Default)]
+pub struct BevyEitherDiagnosticsPlugin {
+    queries: Vec<(&'static str, RegisterQuery)>,
+}
+
+type RegisterQuery = fn(&mut World, &'static str) -> RegisteredQuery;
+
+type ArchetypeVariant = Box<dyn Fn(&Archetype) -> Option<usize> + Send + Sync>;
+
+/// A registered query, with its type erased so that every query is measured by the same system.
+struct RegisteredQuery {
+    variant_ids: Vec<DiagnosticId>,
+    archetype_id: DiagnosticId,
+    archetype_variant: ArchetypeVariant,
+}
+
+impl BevyEitherDiagnosticsPlugin {
+    /// The number of measurements kept by each diagnostic.
+    pub const MAX_HISTORY_LENGTH: usize = 20;
+
+    /// Registers `Q`, naming its diagnostics after `name`.
+    pub fn with<Q: VariantQuery + 'static>(mut self, name: &'static str) -> Self {
+        self.queries.push((name, register_query::<Q>));
+        self
+    }
+
+    /// Returns the id of the diagnostic counting the entities given the `variant` of the query
+    /// registered as `name`.
+    pub fn variant_diagnostic_id(name: &str, variant: &str) -> DiagnosticId {
+        DiagnosticId::from_u128(hash(&[name.as_bytes(), b"/", variant.as_bytes()]))
+    }
+
+    /// Returns the id of the diagnostic counting the archetypes matched by the query registered as
+    /// `name`.
+    pub fn archetype_diagnostic_id(name: &str) -> DiagnosticId {
+        // `#` can't appear in a variant name, so this never collides with a variant's id
+        DiagnosticId::from_u128(hash(&[name.as_bytes(), b"#archetypes"]))
+    }
+}
+
+impl Plugin for BevyEitherDiagnosticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let world = app.world_mut();
+        world.get_resource_or_insert_with(Diagnostics::default);
+        let queries: Vec<_> = self.queries
+            .iter()
+            .map(|&(name, register)| register(world, name))
+            .collect();
+        app.add_system(
+            (move |archetypes: &Archetypes, mut diagnostics: ResMut<Diagnostics>| {
+                for query in &queries {
+                    let mut entities = vec![0; query.variant_ids.len()];
+                    let mut matched = 0;
+                    for archetype in archetypes.iter() {
+                        if let Some(variant) = (query.archetype_variant)(archetype) {
+                            entities[variant] += archetype.len();
+                            matched += 1;
+                        }
+                    }
+                    for (&id, &count) in query.variant_ids.iter().zip(&entities) {
+                        diagnostics.add_measurement(id, count as f64);
+                    }
+                    diagnostics.add_measurement(query.archetype_id, matched as f64);
+                }
+            }).system(),
+        );
+    }
+}
+
+/// 128 bit FNV-1a, giving stable ids across runs.
+fn hash(parts: &[&[u8]]) -> u128 {
+    let mut hash: u128 = 0x6c62272e07bb014262b821756295c58d;
+    for &byte in parts.iter().flat_map(|part| part.iter()) {
+        hash ^= byte as u128;
+        hash = hash.wrapping_mul(0x0000000001000000000000000000013b);
+    }
+    hash
+}
+
+fn register_query<Q: VariantQuery + 'static>(
+    world: &mut World,
+    name: &'static str,
+) -> RegisteredQuery {
+    let state = Q::State::init(world);
+    let variant_ids: Vec<_> = Q::variant_names()
+        .iter()
+        .map(|variant| BevyEitherDiagnosticsPlugin::variant_diagnostic_id(name, variant))
+        .collect();
+    let archetype_id = BevyEitherDiagnosticsPlugin::archetype_diagnostic_id(name);
+
+    let mut diagnostics = world.get_resource_or_insert_with(Diagnostics::default);
+    for (&id, variant) in variant_ids.iter().zip(Q::variant_names()) {
+        diagnostics.add(Diagnostic::new(
+            id,
+            format!("{}/{}", name, variant),
+            BevyEitherDiagnosticsPlugin::MAX_HISTORY_LENGTH,
+        ));
+    }
+    diagnostics.add(Diagnostic::new(
+        archetype_id,
+        format!("{}/archetypes", name),
+        BevyEitherDiagnosticsPlugin::MAX_HISTORY_LENGTH,
+    ));
+
+    RegisteredQuery {
+        variant_ids,
+        archetype_id,
+        archetype_variant: Box::new(move |archetype| Q::archetype_variant(&state, archetype)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    #[test]
+    fn test_diagnostics() {
+        let mut app = App::build();
+        app.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet))
+            .add_plugin(BevyEitherDiagnosticsPlugin::default()
+                .with::<Either<&A, &B>>("either")
+                .with::<EitherBoth<&A, &B>>("both"));
+        let world = app.world_mut();
+        world.spawn().insert(A);
+        world.spawn().insert(A).insert(B);
+        world.spawn().insert(B);
+        world.spawn().insert(B);
+        world.spawn().insert(B).insert(1u32);
+        app.app.update();
+
+        let diagnostics = app.world().get_resource::<Diagnostics>().unwrap();
+        let value = |id| diagnostics.get(id).unwrap().value().unwrap();
+        let variant = BevyEitherDiagnosticsPlugin::variant_diagnostic_id;
+        let archetypes = BevyEitherDiagnosticsPlugin::archetype_diagnostic_id;
+        assert_eq!(value(variant("either", "Left")), 2.0);
+        assert_eq!(value(variant("either", "Right")), 3.0);
+        assert_eq!(value(archetypes("either")), 4.0);
+        assert_eq!(value(variant("both", "Left")), 1.0);
+        assert_eq!(value(variant("both", "Right")), 3.0);
+        assert_eq!(value(variant("both", "Both")), 1.0);
+        assert_eq!(value(archetypes("both")), 4.0);
+    }
+}
@@ -0,0 +1,360 @@
+use super::*;
+use core::{marker::PhantomData, ptr::{self, NonNull}};
+use alloc::vec::Vec;
+use bevy::ecs::entity::Entity;
+use smallvec::SmallVec;
+
+/// One branch of a [`DynamicEither`], matching entities that have all of the
+/// [required](DynamicBranch::required) components and none of the
+/// [excluded](DynamicBranch::excluded) ones.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct DynamicBranch {
+    /// The components that must be present. These are the components given in the item.
+    pub required: Vec<ComponentId>,
+    /// The components that must be absent.
+    pub excluded: Vec<ComponentId>,
+}
+
+impl DynamicBranch {
+    /// Creates a branch matching entities with all of the `required` components and none of the
+    /// `excluded` ones.
+    pub fn new(required: Vec<ComponentId>, excluded: Vec<ComponentId>) -> Self {
+        DynamicBranch { required, excluded }
+    }
+}
+
+/// The [resource](World::insert_resource) holding the branches of [`DynamicEither<M>`], in
+/// priority order.
+///
+/// The branches are read when the [`DynamicEither<M>`]'s state is initialized, so this resource
+/// must be inserted before any [query](Query) over it is created. Changing the resource afterwards
+/// doesn't affect existing queries.
+pub struct DynamicEitherBranches<M> {
+    pub branches: Vec<DynamicBranch>,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> DynamicEitherBranches<M> {
+    pub fn new(branches: Vec<DynamicBranch>) -> Self {
+        DynamicEitherBranches {
+            branches,
+            marker: PhantomData,
+        }
+    }
+}
+
+/// A [world query](WorldQuery) matching the first of many [branches](DynamicBranch) that are
+/// built at runtime.
+///
+/// The branches are taken from the [`DynamicEitherBranches<M>`] resource, where `M` is a marker
+/// type telling apart different dynamic queries. The item is a [`DynamicEitherItem`].
+///
+/// Every required component of every branch is accessed mutably, but writing through the
+/// [item's pointers](DynamicEitherItem::components) doesn't trigger change detection.
+pub struct DynamicEither<M>(PhantomData<fn() -> M>);
+
+/// The item of a [`DynamicEither`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DynamicEitherItem {
+    /// The index of the matched branch.
+    pub branch: usize,
+    /// Pointers to the matched branch's [required](DynamicBranch::required) components, in the
+    /// same order. Branches requiring up to four components don't allocate.
+    pub components: SmallVec<[NonNull<u8>; 4]>,
+}
+
+struct BranchState {
+    required: Vec<(ComponentId, StorageType)>,
+    excluded: Vec<(ComponentId, StorageType)>,
+}
+
+impl BranchState {
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.required.iter().all(|&(id, _)| archetype.contains(id))
+            && !self.excluded.iter().any(|&(id, _)| archetype.contains(id))
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        self.required.iter().all(|&(id, _)| table.has_column(id))
+            && !self.excluded.iter().any(|&(id, _)| table.has_column(id))
+    }
+}
+
+pub struct DynamicEitherState<M> {
+    branches: Vec<BranchState>,
+    marker: PhantomData<fn() -> M>,
+}
+
+unsafe impl<M: 'static> FetchState for DynamicEitherState<M> {
+    fn init(world: &mut World) -> Self {
+        let branches = world
+            .get_resource::<DynamicEitherBranches<M>>()
+            .unwrap_or_else(|| panic!(
+                "DynamicEitherBranches<{}> must be inserted before querying for DynamicEither<{0}>",
+                core::any::type_name::<M>(),
+            ));
+        let components = world.components();
+        let storage_type = |&id: &ComponentId| {
+            let info = components
+                .get_info(id)
+                .unwrap_or_else(|| panic!("{:?} isn't a component of this world", id));
+            (id, info.storage_type())
+        };
+        let branches = branches.branches
+            .iter()
+            .map(|branch| BranchState {
+                required: branch.required.iter().map(storage_type).collect(),
+                excluded: branch.excluded.iter().map(storage_type).collect(),
+            })
+            .collect();
+        DynamicEitherState {
+            branches,
+            marker: PhantomData,
+        }
+    }
+
+    fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
+        // the branches are only checked against the rest of the query, as they're never fetched
+        // together
+        let base = access.clone();
+        for branch in self.branches.iter() {
+            for &(id, _) in branch.required.iter() {
+                if base.access().has_read(id) {
+                    panic!(
+                        "DynamicEither<{}> writes {:?}, which conflicts with a previous access in \
+                        this query. Mutable component access must be unique.",
+                        core::any::type_name::<M>(),
+                        id,
+                    );
+                }
+                access.add_write(id);
+            }
+        }
+    }
+
+    fn update_archetype_component_access(
+        &self,
+        archetype: &Archetype,
+        access: &mut Access<ArchetypeComponentId>,
+    ) {
+        for branch in self.branches.iter() {
+            for &(id, _) in branch.required.iter() {
+                if let Some(archetype_component_id) = archetype.get_archetype_component_id(id) {
+                    access.add_write(archetype_component_id);
+                }
+            }
+        }
+    }
+
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.branches.iter().any(|branch| branch.matches_archetype(archetype))
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        self.branches.iter().any(|branch| branch.matches_table(table))
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Source {
+    Table(*const Column),
+    SparseSet(*const ComponentSparseSet),
+}
+
+pub struct DynamicEitherFetch<M> {
+    dense: bool,
+    branch: usize,
+    sources: Vec<Source>,
+    entities: *const Entity,
+    entity_table_rows: *const usize,
+    sparse_sets: *const SparseSets,
+    marker: PhantomData<fn() -> M>,
+}
+
+impl<M> DynamicEitherFetch<M> {
+    unsafe fn sparse_set(&self, id: ComponentId) -> Source {
+        // the sparse set exists if an archetype containing the component does
+        Source::SparseSet((*self.sparse_sets).get(id).unwrap())
+    }
+}
+
+impl<'w, M: 'static> Fetch<'w> for DynamicEitherFetch<M> {
+    type Item = DynamicEitherItem;
+    type State = DynamicEitherState<M>;
+
+    fn is_dense(&self) -> bool {
+        self.dense
+    }
+
+    unsafe fn init(
+        world: &World,
+        state: &Self::State,
+        _last_change_tick: u32,
+        _change_tick: u32,
+    ) -> Self {
+        DynamicEitherFetch {
+            dense: state.branches.iter().all(|branch| {
+                branch.required.iter()
+                    .chain(branch.excluded.iter())
+                    .all(|&(_, storage_type)| storage_type == StorageType::Table)
+            }),
+            branch: 0,
+            sources: Vec::new(),
+            entities: ptr::null(),
+            entity_table_rows: ptr::null(),
+            sparse_sets: &world.storages().sparse_sets,
+            marker: PhantomData,
+        }
+    }
+
+    unsafe fn set_archetype(
+        &mut self,
+        state: &Self::State,
+        archetype: &Archetype,
+        tables: &Tables,
+    ) {
+        let branch = state.branches.iter().position(|branch| branch.matches_archetype(archetype));
+        if let Some(branch) = branch {
+            let table = &tables[archetype.table_id()];
+            self.branch = branch;
+            self.entities = archetype.entities().as_ptr();
+            self.entity_table_rows = archetype.entity_table_rows().as_ptr();
+            self.sources.clear();
+            for &(id, storage_type) in state.branches[branch].required.iter() {
+                let source = match storage_type {
+                    StorageType::Table => Source::Table(table.get_column(id).unwrap()),
+                    StorageType::SparseSet => self.sparse_set(id),
+                };
+                self.sources.push(source);
+            }
+        } else {
+            unmatched(format_args!("None of the branches were matched. At least one should be."));
+        }
+    }
+
+    unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
+        let branch = state.branches.iter().position(|branch| branch.matches_table(table));
+        if let Some(branch) = branch {
+            self.branch = branch;
+            self.sources.clear();
+            for &(id, _) in state.branches[branch].required.iter() {
+                self.sources.push(Source::Table(table.get_column(id).unwrap()));
+            }
+        } else {
+            unmatched(format_args!("None of the branches were matched. At least one should be."));
+        }
+    }
+
+    unsafe fn archetype_fetch(&mut self, archetype_index: usize) -> Self::Item {
+        let table_row = *self.entity_table_rows.add(archetype_index);
+        let entity = *self.entities.add(archetype_index);
+        let components = self.sources
+            .iter()
+            .map(|&source| match source {
+                Source::Table(column) => (*column).get_unchecked(table_row),
+                Source::SparseSet(sparse_set) => (*sparse_set).get(entity).unwrap(),
+            })
+            .map(|ptr| NonNull::new_unchecked(ptr))
+            .collect();
+        DynamicEitherItem {
+            branch: self.branch,
+            components,
+        }
+    }
+
+    unsafe fn table_fetch(&mut self, table_row: usize) -> Self::Item {
+        let components = self.sources
+            .iter()
+            .map(|&source| match source {
+                Source::Table(column) => NonNull::new_unchecked((*column).get_unchecked(table_row)),
+                Source::SparseSet(_) => unreachable!("sparse components are never dense"),
+            })
+            .collect();
+        DynamicEitherItem {
+            branch: self.branch,
+            components,
+        }
+    }
+}
+
+impl<M: 'static> WorldQuery for DynamicEither<M> {
+    type Fetch = DynamicEitherFetch<M>;
+    type State = DynamicEitherState<M>;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use alloc::vec;
+    use super::*;
+
+    struct Marker;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct A(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct B(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct C(u32);
+
+    #[test]
+    fn test_dynamic_either() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<C>(StorageType::SparseSet)).unwrap();
+        let a = world.spawn().insert(A(1)).id();
+        let ab = world.spawn().insert(A(2)).insert(B(3)).id();
+        let bc = world.spawn().insert(B(4)).insert(C(5)).id();
+        let c = world.spawn().insert(C(6)).id();
+        world.spawn().insert(A(7)).insert(C(8));
+        let ids = world.components();
+        let a_id = ids.get_id(core::any::TypeId::of::<A>()).unwrap();
+        let b_id = ids.get_id(core::any::TypeId::of::<B>()).unwrap();
+        let c_id = ids.get_id(core::any::TypeId::of::<C>()).unwrap();
+        world.insert_resource(DynamicEitherBranches::<Marker>::new(vec![
+            DynamicBranch::new(vec![a_id], vec![c_id]),
+            DynamicBranch::new(vec![c_id, b_id], Vec::new()),
+            DynamicBranch::new(vec![c_id], vec![a_id]),
+        ]));
+        let mut query = world.query::<(Entity, DynamicEither<Marker>)>();
+        let mut matched = 0;
+        for (entity, item) in query.iter_mut(&mut world) {
+            let components = &item.components;
+            unsafe {
+                match item.branch {
+                    0 => {
+                        assert!(entity == a || entity == ab);
+                        let a = components[0].cast::<A>().as_ref();
+                        assert_eq!(a.0, if entity == ab { 2 } else { 1 });
+                    },
+                    1 => {
+                        assert_eq!(entity, bc);
+                        assert_eq!(components[0].cast::<C>().as_ref(), &C(5));
+                        components[1].cast::<B>().as_mut().0 += 10;
+                    },
+                    2 => {
+                        assert_eq!(entity, c);
+                        assert_eq!(components[0].cast::<C>().as_ref(), &C(6));
+                    },
+                    _ => unreachable!(),
+                }
+            }
+            matched += 1;
+        }
+        assert_eq!(matched, 4);
+        assert_eq!(world.get::<B>(bc), Some(&B(14)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exclusive_access() {
+        let mut world = World::default();
+        let a = world.register_component(ComponentDescriptor::new::<A>(StorageType::Table))
+            .unwrap();
+        world.insert_resource(DynamicEitherBranches::<Marker>::new(vec![
+            DynamicBranch::new(vec![a], Vec::new()),
+        ]));
+        world.query::<(&A, DynamicEither<Marker>)>();
+    }
+}
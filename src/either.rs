@@ -1,12 +1,115 @@
-use super::{*, either_both::EitherBothState};
+use super::{*, access::{add_exclusive_branch_access, branch_components}, config::VariantFetch};
 
 /// A type that contains either the [first](Either::Left) or [second](Either::Right) type.
+///
+/// As a [world query](WorldQuery), the components read or written by either side also act as
+/// [`With`] filters when checking for conflicts with other queries, as Bevy 0.5 can't record
+/// access without one. So `Query<Either<&A, &C>>` and `Query<&mut C, Without<A>>` aren't caught
+/// conflicting, even though both reach the entities with only `C`. Use a
+/// [`QuerySet`](bevy::ecs::system::QuerySet) for such queries.
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
 pub enum Either<T, U> {
     Left(T),
     Right(U),
 }
 
+/// The variant of an [`Either`], without its item.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum EitherKind {
+    Left,
+    Right,
+}
+
+impl<T, U> Either<T, U> {
+    /// Returns which variant this is.
+    pub fn kind(&self) -> EitherKind {
+        match self {
+            Either::Left(_) => EitherKind::Left,
+            Either::Right(_) => EitherKind::Right,
+        }
+    }
+}
+
+pub struct EitherState<T: FetchState, U: FetchState> {
+    pub(super) left_state: T,
+    pub(super) right_state: U,
+    component_count: usize,
+}
+
+impl<T: FetchState, U: FetchState> EitherState<T, U> {
+    /// Returns the variant given to the entities of `archetype`, or [`None`] if neither side
+    /// matches it.
+    pub fn archetype_kind(&self, archetype: &Archetype) -> Option<EitherKind> {
+        if self.left_state.matches_archetype(archetype) {
+            Some(EitherKind::Left)
+        } else if self.right_state.matches_archetype(archetype) {
+            Some(EitherKind::Right)
+        } else {
+            None
+        }
+    }
+
+    /// Returns how every archetype in `archetypes` is resolved.
+    pub fn archetype_branches<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+    ) -> impl Iterator<Item = ArchetypeBranch<'a, EitherKind>> + 'a {
+        let branches = [
+            branch_components(self.component_count, |access| {
+                self.left_state.update_component_access(access);
+            }),
+            branch_components(self.component_count, |access| {
+                self.right_state.update_component_access(access);
+            }),
+        ];
+        archetypes.iter().map(move |archetype| {
+            let kind = self.archetype_kind(archetype);
+            let components = kind.map_or(&[][..], |kind| &branches[kind as usize][..]);
+            ArchetypeBranch::new(archetype, kind, components)
+        })
+    }
+}
+
+unsafe impl<T: FetchState, U: FetchState> FetchState for EitherState<T, U> {
+    fn init(world: &mut World) -> Self {
+        let left_state = T::init(world);
+        let right_state = U::init(world);
+        EitherState {
+            left_state,
+            right_state,
+            component_count: world.components().len(),
+        }
+    }
+
+    fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
+        // only one side is ever fetched for an entity, so the sides can't conflict
+        let base = access.clone();
+        add_exclusive_branch_access(access, &base, self.component_count, |access| {
+            self.left_state.update_component_access(access);
+        });
+        add_exclusive_branch_access(access, &base, self.component_count, |access| {
+            self.right_state.update_component_access(access);
+        });
+    }
+
+    fn update_archetype_component_access(
+        &self,
+        archetype: &Archetype,
+        access: &mut Access<ArchetypeComponentId>,
+    ) {
+        self.left_state.update_archetype_component_access(archetype, access);
+        self.right_state.update_archetype_component_access(archetype, access);
+    }
+
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        self.left_state.matches_archetype(archetype) || self.right_state.matches_archetype(archetype)
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        self.left_state.matches_table(table) || self.right_state.matches_table(table)
+    }
+}
+
 pub struct EitherFetch<T, U> {
     left: T,
     right: U,
@@ -20,7 +123,7 @@ enum Matches {
 
 impl<'w, T: Fetch<'w>, U: Fetch<'w>> Fetch<'w> for EitherFetch<T, U> {
     type Item = Either<T::Item, U::Item>;
-    type State = EitherBothState<T::State, U::State>;
+    type State = EitherState<T::State, U::State>;
 
     fn is_dense(&self) -> bool {
         self.left.is_dense() && self.right.is_dense()
@@ -45,30 +148,26 @@ impl<'w, T: Fetch<'w>, U: Fetch<'w>> Fetch<'w> for EitherFetch<T, U> {
         archetype: &Archetype,
         tables: &Tables,
     ) {
-        let left_match = state.left_state.matches_archetype(archetype);
-        let right_match = state.right_state.matches_archetype(archetype);
-        if left_match {
+        if state.left_state.matches_archetype(archetype) {
             self.left.set_archetype(&state.left_state, archetype, tables);
             self.matches = Matches::Left;
-        } else if right_match {
+        } else if UNCHECKED || state.right_state.matches_archetype(archetype) {
             self.matches = Matches::Right;
             self.right.set_archetype(&state.right_state, archetype, tables);
-        } else if cfg!(not(all(not(debug_assertions), unchecked))) {
-            unreachable!("neither left nor right side matched. what?");
+        } else {
+            unmatched(format_args!("neither left nor right side matched. what?"));
         }
     }
 
     unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
-        let left_match = state.left_state.matches_table(table);
-        let right_match = state.right_state.matches_table(table);
-        if left_match {
+        if state.left_state.matches_table(table) {
             self.left.set_table(&state.left_state, table);
             self.matches = Matches::Left;
-        } else if right_match {
+        } else if UNCHECKED || state.right_state.matches_table(table) {
             self.matches = Matches::Right;
             self.right.set_table(&state.right_state, table);
-        } else if cfg!(not(all(not(debug_assertions), unchecked))) {
-            unreachable!("neither left nor right side matched. what?");
+        } else {
+            unmatched(format_args!("neither left nor right side matched. what?"));
         }
     }
 
@@ -87,11 +186,61 @@ impl<'w, T: Fetch<'w>, U: Fetch<'w>> Fetch<'w> for EitherFetch<T, U> {
     }
 }
 
+unsafe impl<'w, T: Fetch<'w>, U: Fetch<'w>> VariantFetch<'w> for EitherFetch<T, U> {
+    const VARIANT_COUNT: usize = 2;
+
+    fn matches_archetype_variant(
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+    ) -> bool {
+        match variant {
+            0 => state.left_state.matches_archetype(archetype),
+            1 => state.right_state.matches_archetype(archetype),
+            _ => false,
+        }
+    }
+
+    fn matches_table_variant(state: &Self::State, variant: usize, table: &Table) -> bool {
+        match variant {
+            0 => state.left_state.matches_table(table),
+            1 => state.right_state.matches_table(table),
+            _ => false,
+        }
+    }
+
+    unsafe fn set_archetype_variant(
+        &mut self,
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+        tables: &Tables,
+    ) {
+        if variant == 0 {
+            self.left.set_archetype(&state.left_state, archetype, tables);
+            self.matches = Matches::Left;
+        } else {
+            self.right.set_archetype(&state.right_state, archetype, tables);
+            self.matches = Matches::Right;
+        }
+    }
+
+    unsafe fn set_table_variant(&mut self, state: &Self::State, variant: usize, table: &Table) {
+        if variant == 0 {
+            self.left.set_table(&state.left_state, table);
+            self.matches = Matches::Left;
+        } else {
+            self.right.set_table(&state.right_state, table);
+            self.matches = Matches::Right;
+        }
+    }
+}
+
 unsafe impl<T: ReadOnlyFetch, U: ReadOnlyFetch> ReadOnlyFetch for EitherFetch<T, U> {}
 
 impl<T: WorldQuery, U: WorldQuery> WorldQuery for Either<T, U> {
     type Fetch = EitherFetch<T::Fetch, U::Fetch>;
-    type State = EitherBothState<T::State, U::State>;
+    type State = EitherState<T::State, U::State>;
 }
 
 #[cfg(test)]
@@ -202,4 +351,70 @@ mod tests {
         assert_eq!(world.get_resource::<LeftCount>().unwrap().0, real_left_count + real_both_count);
         assert_eq!(world.get_resource::<RightCount>().unwrap().0, real_right_count);
     }
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Health(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct Shield(u32);
+
+    #[test]
+    fn test_exclusive_access() {
+        let mut world = World::default();
+        let health = world.spawn().insert(Health(1)).id();
+        let shielded = world.spawn().insert(Health(2)).insert(Shield(3)).id();
+        let mut query = world.query::<(Entity, Either<(&Health, &Shield), &mut Health>)>();
+        for (entity, item) in query.iter_mut(&mut world) {
+            match item {
+                Either::Left((h, s)) => {
+                    assert_eq!(entity, shielded);
+                    assert_eq!((h, s), (&Health(2), &Shield(3)));
+                },
+                Either::Right(mut h) => {
+                    assert_eq!(entity, health);
+                    h.0 += 10;
+                },
+            }
+        }
+        assert_eq!(world.get::<Health>(health), Some(&Health(11)));
+        assert_eq!(world.get::<Health>(shielded), Some(&Health(2)));
+
+        let mut query = world.query::<Either<&mut Health, &mut Health>>();
+        for item in query.iter_mut(&mut world) {
+            match item {
+                Either::Left(mut h) => h.0 += 1,
+                Either::Right(_) => unreachable!(),
+            }
+        }
+        assert_eq!(world.get::<Health>(health), Some(&Health(12)));
+        assert_eq!(world.get::<Health>(shielded), Some(&Health(3)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exclusive_access_in_tuple() {
+        let mut world = World::default();
+        world.query::<(&Health, Either<&mut Health, &Shield>)>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_exclusive_access_across_params() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((
+            |_: Query<Either<&Health, &Shield>>, _: Query<&mut Health>| {}
+        ).system());
+        update_stage.run(&mut world);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_branch_filters_across_params() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|
+            _: Query<Either<(&mut Health, With<Shield>), &mut Health>>,
+            _: Query<&mut Health, Without<Shield>>,
+        | {}).system());
+        update_stage.run(&mut world);
+    }
 }
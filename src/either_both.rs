@@ -1,4 +1,4 @@
-use super::*;
+use super::{*, access::{add_exclusive_branch_access, branch_components}};
 
 /// A type that contains either the [first](EitherBoth::Left) type, [second](EitherBoth::Right)
 /// type, or [both](EitherBoth::Both).
@@ -9,6 +9,25 @@ pub enum EitherBoth<T, U> {
     Both(T, U),
 }
 
+/// The variant of an [`EitherBoth`], without its items.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+pub enum EitherBothKind {
+    Left,
+    Right,
+    Both,
+}
+
+impl<T, U> EitherBoth<T, U> {
+    /// Returns which variant this is.
+    pub fn kind(&self) -> EitherBothKind {
+        match self {
+            EitherBoth::Left(_) => EitherBothKind::Left,
+            EitherBoth::Right(_) => EitherBothKind::Right,
+            EitherBoth::Both(_, _) => EitherBothKind::Both,
+        }
+    }
+}
+
 enum Matches {
     Left,
     Right,
@@ -18,6 +37,47 @@ enum Matches {
 pub struct EitherBothState<T: FetchState, U: FetchState> {
     pub(super) left_state: T,
     pub(super) right_state: U,
+    component_count: usize,
+}
+
+impl<T: FetchState, U: FetchState> EitherBothState<T, U> {
+    /// Returns the variant given to the entities of `archetype`, or [`None`] if neither side
+    /// matches it.
+    pub fn archetype_kind(&self, archetype: &Archetype) -> Option<EitherBothKind> {
+        match (
+            self.left_state.matches_archetype(archetype),
+            self.right_state.matches_archetype(archetype),
+        ) {
+            (true, false) => Some(EitherBothKind::Left),
+            (false, true) => Some(EitherBothKind::Right),
+            (true, true) => Some(EitherBothKind::Both),
+            (false, false) => None,
+        }
+    }
+
+    /// Returns how every archetype in `archetypes` is resolved.
+    pub fn archetype_branches<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+    ) -> impl Iterator<Item = ArchetypeBranch<'a, EitherBothKind>> + 'a {
+        let branches = [
+            branch_components(self.component_count, |access| {
+                self.left_state.update_component_access(access);
+            }),
+            branch_components(self.component_count, |access| {
+                self.right_state.update_component_access(access);
+            }),
+            branch_components(self.component_count, |access| {
+                self.left_state.update_component_access(access);
+                self.right_state.update_component_access(access);
+            }),
+        ];
+        archetypes.iter().map(move |archetype| {
+            let kind = self.archetype_kind(archetype);
+            let components = kind.map_or(&[][..], |kind| &branches[kind as usize][..]);
+            ArchetypeBranch::new(archetype, kind, components)
+        })
+    }
 }
 
 unsafe impl<T: FetchState, U: FetchState> FetchState for EitherBothState<T, U> {
@@ -25,12 +85,18 @@ unsafe impl<T: FetchState, U: FetchState> FetchState for EitherBothState<T, U> {
         EitherBothState {
             left_state: T::init(world),
             right_state: U::init(world),
+            component_count: world.components().len(),
         }
     }
 
     fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
-        self.left_state.update_component_access(access);
-        self.right_state.update_component_access(access);
+        // both sides can be fetched together, so they're checked against each other, but as
+        // either can be missing, only their reads and writes are added
+        let base = access.clone();
+        add_exclusive_branch_access(access, &base, self.component_count, |access| {
+            self.left_state.update_component_access(access);
+            self.right_state.update_component_access(access);
+        });
     }
 
     fn update_archetype_component_access(
@@ -86,40 +152,36 @@ impl<'w, T: Fetch<'w>, U: Fetch<'w>> Fetch<'w> for EitherBothFetch<T, U> {
         archetype: &Archetype,
         tables: &Tables,
     ) {
-        let left_match = state.left_state.matches_archetype(archetype);
-        let right_match = state.right_state.matches_archetype(archetype);
-        if left_match {
+        if state.left_state.matches_archetype(archetype) {
             self.left.set_archetype(&state.left_state, archetype, tables);
-            if right_match {
+            if state.right_state.matches_archetype(archetype) {
                 self.matches = Matches::Both;
                 self.right.set_archetype(&state.right_state, archetype, tables);
             } else {
                 self.matches = Matches::Left;
             }
-        } else if right_match {
+        } else if UNCHECKED || state.right_state.matches_archetype(archetype) {
             self.matches = Matches::Right;
             self.right.set_archetype(&state.right_state, archetype, tables);
-        } else if cfg!(not(all(not(debug_assertions), unchecked))) {
-            unreachable!("neither left nor right side matched. what?");
+        } else {
+            unmatched(format_args!("neither left nor right side matched. what?"));
         }
     }
 
     unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
-        let left_match = state.left_state.matches_table(table);
-        let right_match = state.right_state.matches_table(table);
-        if left_match {
+        if state.left_state.matches_table(table) {
             self.left.set_table(&state.left_state, table);
-            if right_match {
+            if state.right_state.matches_table(table) {
                 self.matches = Matches::Both;
                 self.right.set_table(&state.right_state, table);
             } else {
                 self.matches = Matches::Left;
             }
-        } else if right_match {
+        } else if UNCHECKED || state.right_state.matches_table(table) {
             self.matches = Matches::Right;
             self.right.set_table(&state.right_state, table);
-        } else if cfg!(not(all(not(debug_assertions), unchecked))) {
-            unreachable!("neither left nor right side matched. what?");
+        } else {
+            unmatched(format_args!("neither left nor right side matched. what?"));
         }
     }
 
@@ -146,6 +208,70 @@ impl<'w, T: Fetch<'w>, U: Fetch<'w>> Fetch<'w> for EitherBothFetch<T, U> {
     }
 }
 
+unsafe impl<'w, T: Fetch<'w>, U: Fetch<'w>> VariantFetch<'w> for EitherBothFetch<T, U> {
+    const VARIANT_COUNT: usize = 3;
+
+    fn matches_archetype_variant(
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+    ) -> bool {
+        let left = state.left_state.matches_archetype(archetype);
+        let right = state.right_state.matches_archetype(archetype);
+        match variant {
+            0 => left && !right,
+            1 => !left && right,
+            2 => left && right,
+            _ => false,
+        }
+    }
+
+    fn matches_table_variant(state: &Self::State, variant: usize, table: &Table) -> bool {
+        let left = state.left_state.matches_table(table);
+        let right = state.right_state.matches_table(table);
+        match variant {
+            0 => left && !right,
+            1 => !left && right,
+            2 => left && right,
+            _ => false,
+        }
+    }
+
+    unsafe fn set_archetype_variant(
+        &mut self,
+        state: &Self::State,
+        variant: usize,
+        archetype: &Archetype,
+        tables: &Tables,
+    ) {
+        if variant != 1 {
+            self.left.set_archetype(&state.left_state, archetype, tables);
+        }
+        if variant != 0 {
+            self.right.set_archetype(&state.right_state, archetype, tables);
+        }
+        self.matches = match variant {
+            0 => Matches::Left,
+            1 => Matches::Right,
+            _ => Matches::Both,
+        };
+    }
+
+    unsafe fn set_table_variant(&mut self, state: &Self::State, variant: usize, table: &Table) {
+        if variant != 1 {
+            self.left.set_table(&state.left_state, table);
+        }
+        if variant != 0 {
+            self.right.set_table(&state.right_state, table);
+        }
+        self.matches = match variant {
+            0 => Matches::Left,
+            1 => Matches::Right,
+            _ => Matches::Both,
+        };
+    }
+}
+
 impl<T: WorldQuery, U: WorldQuery> WorldQuery for EitherBoth<T, U> {
     type Fetch = EitherBothFetch<T::Fetch, U::Fetch>;
     type State = EitherBothState<T::State, U::State>;
@@ -154,6 +280,7 @@ impl<T: WorldQuery, U: WorldQuery> WorldQuery for EitherBoth<T, U> {
 #[cfg(test)]
 mod tests {
     use bevy::prelude::*;
+    use alloc::vec::Vec;
     use super::*;
 
     #[derive(Clone, Copy)]
@@ -162,6 +289,8 @@ mod tests {
     #[derive(Clone, Copy)]
     struct RightElem;
 
+    struct Extra;
+
     fn push_entities(world: &mut World) -> (u32, u32, u32) {
         // 0 = None, 1 = Some(Left), 2 = Some(Right), 3 = Some(Both)
         static SUPERPERM: &[Option<EitherBoth<LeftElem, RightElem>>] = &[
@@ -268,4 +397,52 @@ mod tests {
         assert_eq!(world.get_resource::<RightCount>().unwrap().0, real_right_count);
         assert_eq!(world.get_resource::<BothCount>().unwrap().0, real_both_count);
     }
+
+    #[test]
+    fn test_archetype_branches() {
+        let mut world = World::default();
+        push_entities(&mut world);
+        world.spawn().insert(LeftElem).insert(Extra);
+        let state = <EitherBoth<&LeftElem, &RightElem> as WorldQuery>::State::init(&mut world);
+        let left = world.components().get_id(core::any::TypeId::of::<LeftElem>()).unwrap();
+        let right = world.components().get_id(core::any::TypeId::of::<RightElem>()).unwrap();
+        let (mut matched, mut unmatched) = (0, 0);
+        for branch in state.archetype_branches(world.archetypes()) {
+            // the unrelated `Extra` is never listed
+            let components = branch.archetype_components().collect::<Vec<_>>();
+            match branch.kind {
+                Some(EitherBothKind::Left) => assert_eq!(components, [left]),
+                Some(EitherBothKind::Right) => assert_eq!(components, [right]),
+                Some(EitherBothKind::Both) => assert_eq!(components, [left, right]),
+                None => {
+                    assert!(components.is_empty());
+                    unmatched += 1;
+                },
+            }
+            if branch.kind.is_some() {
+                matched += 1;
+                assert!(!world.archetypes()[branch.id()].is_empty());
+            }
+        }
+        assert_eq!(matched, 4);
+        assert_eq!(matched + unmatched, world.archetypes().len());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shared_access() {
+        let mut world = World::default();
+        world.query::<EitherBoth<&LeftElem, &mut LeftElem>>();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_branch_filters_across_params() {
+        let mut world = World::default();
+        let mut update_stage = SystemStage::single((|
+            _: Query<EitherBoth<(&LeftElem, With<RightElem>), &Extra>>,
+            _: Query<&mut Extra, Without<RightElem>>,
+        | {}).system());
+        update_stage.run(&mut world);
+    }
 }
@@ -3,14 +3,11 @@ macro_rules! either_many {
     (@__first $first:tt $(, $($others:tt),+)?) => {
         $first
     };
-    (readonly $(#[$($m:meta),*])? $name:ident $(< $($lf:lifetime),* $(, )? >)?, $($varn:ident($($vart:tt)+)),+ $(, )?) => {
-        $crate::either_many!($(#[$($m),*])? $name $(< $($lf),*>)?, $($varn($($vart)+)),+);
-
-        $crate::exports::paste!{
-            unsafe impl $(< $($lf),*>)? ::bevy::ecs::query::ReadOnlyFetch for [<__ $name:lower>]::[<$name Fetch>] $(< $($lf),*>)? {}
-        }
-    };
-    ($(#[$($m:meta),*])? $name:ident $(< $($lf:lifetime),* >)?, $($varn:ident($($vart:tt)+)),+ $(, )?) => {
+    (@__define
+        $(#[$($m:meta),*])? $name:ident [$($gen:tt)*] [$($static_gen:tt)*]
+        $item_lf:lifetime $fetch_lf:lifetime,
+        $($varn:ident($($vart:tt)+)),+ $(, )?
+    ) => {
         $crate::exports::paste!{
             $(#[$($m),*])?
             #[doc =
@@ -20,12 +17,157 @@ macro_rules! either_many {
                 "that [`" $name "`] can fulfill.\n\nIn priority order, [`" $name "`] matches:"
                 $("\n * [`" $name "::" $varn "(_)`](" $name "::" $varn ")")+
             ]
-            pub enum $name $(< $($lf),*>)? {
+            pub enum $name $($gen)* {
                 $($varn(<
                     <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
-                    as ::bevy::ecs::query::Fetch<'static>
+                    as ::bevy::ecs::query::Fetch<$item_lf>
                 >::Item)),+
             }
+
+            #[allow(unreachable_patterns)]
+            impl $($gen)* $name $($gen)* {$(
+                #[doc = "Returns whether this is a [`" $name "::" $varn "`]."]
+                pub fn [<is_ $varn:snake>](&self) -> bool {
+                    matches!(self, $name::$varn(_))
+                }
+
+                #[doc = "Returns a reference to the [`" $name "::" $varn "`] item, if it is one."]
+                pub fn [<as_ $varn:snake>](&self) -> Option<&<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item> {
+                    match self {
+                        $name::$varn(item) => Some(item),
+                        _ => None,
+                    }
+                }
+
+                #[doc = "Returns a mutable reference to the [`" $name "::" $varn "`] item, if it is "]
+                #[doc = "one."]
+                pub fn [<as_ $varn:snake _mut>](&mut self) -> Option<&mut <
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item> {
+                    match self {
+                        $name::$varn(item) => Some(item),
+                        _ => None,
+                    }
+                }
+
+                #[doc = "Returns the [`" $name "::" $varn "`] item, or `self` if it isn't one."]
+                pub fn [<into_ $varn:snake>](self) -> Result<<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item, Self> {
+                    match self {
+                        $name::$varn(item) => Ok(item),
+                        other => Err(other),
+                    }
+                }
+
+                #[doc = "Applies `f` to the [`" $name "::" $varn "`] item, if it is one."]
+                pub fn [<map_ $varn:snake>](
+                    self,
+                    f: impl FnOnce(<
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item) -> <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item,
+                ) -> Self {
+                    match self {
+                        $name::$varn(item) => $name::$varn(f(item)),
+                        other => other,
+                    }
+                }
+            )+}
+
+            impl $($gen)* $name $($gen)* {
+                /// Returns the name of the matched variant.
+                pub fn variant_name(&self) -> &'static str {
+                    match self {
+                        $($name::$varn(_) => stringify!($varn),)+
+                    }
+                }
+
+                /// Returns which variant this is.
+                pub fn kind(&self) -> [<$name Kind>] {
+                    match self {
+                        $($name::$varn(_) => [<$name Kind>]::$varn,)+
+                    }
+                }
+            }
+
+            #[doc = "The variant of a [`" $name "`], without its item."]
+            #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+            pub enum [<$name Kind>] {
+                $($varn),+
+            }
+
+            #[doc =
+                "Gets a specific variant of an entity's item in a "
+                "[`Query`](::bevy::ecs::system::Query) over [`" $name "`]."
+            ]
+            pub trait [<$name QueryExt>] {$(
+                #[doc = "Gets the entity's [`" $name "::" $varn "`] item."]
+                #[allow(clippy::mut_from_ref)]
+                fn [<get_ $varn:snake>]<$fetch_lf>(
+                    &$fetch_lf self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item, $crate::EitherQueryError>
+                where
+                    Self: $crate::exports::ReadOnlyGet<$fetch_lf, Item = [<__ $name:lower>]::Item<$fetch_lf>>;
+
+                #[doc = "Gets the entity's [`" $name "::" $varn "`] item mutably."]
+                fn [<get_ $varn:snake _mut>]<$fetch_lf>(
+                    &$fetch_lf mut self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item, $crate::EitherQueryError>;
+            )+}
+
+            impl<'__q, __Q, __F> [<$name QueryExt>] for ::bevy::ecs::system::Query<'__q, __Q, __F>
+            where
+                __Q: ::bevy::ecs::query::WorldQuery<
+                    Fetch = [<__ $name:lower>]::[<$name Fetch>] $($static_gen)*,
+                >,
+                __F: ::bevy::ecs::query::WorldQuery,
+                __F::Fetch: ::bevy::ecs::query::FilterFetch,
+            {$(
+                #[allow(clippy::mut_from_ref)]
+                fn [<get_ $varn:snake>]<$fetch_lf>(
+                    &$fetch_lf self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item, $crate::EitherQueryError>
+                where
+                    Self: $crate::exports::ReadOnlyGet<$fetch_lf, Item = [<__ $name:lower>]::Item<$fetch_lf>>,
+                {
+                    let item = $crate::exports::ReadOnlyGet::get_read_only(self, entity)?;
+                    item.[<into_ $varn:snake>]()
+                        .map_err(|other| $crate::EitherQueryError::OtherVariant(other.variant_name()))
+                }
+
+                fn [<get_ $varn:snake _mut>]<$fetch_lf>(
+                    &$fetch_lf mut self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<<
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                    as ::bevy::ecs::query::Fetch<$item_lf>
+                >::Item, $crate::EitherQueryError> {
+                    self.get_mut(entity)?
+                        .[<into_ $varn:snake>]()
+                        .map_err(|other| $crate::EitherQueryError::OtherVariant(other.variant_name()))
+                }
+            )+}
         }
 
         $crate::exports::paste!{mod [<__ $name:lower>] {
@@ -33,6 +175,8 @@ macro_rules! either_many {
             use ::bevy::prelude::*;
             use ::bevy::ecs::{storage::*, component::*, archetype::*, query::*};
 
+            pub type Item<$fetch_lf> = <[<$name Fetch>] $($static_gen)* as Fetch<$fetch_lf>>::Item;
+
             #[derive(Copy, Clone)]
             enum Matches {
                 $($varn),+
@@ -43,22 +187,64 @@ macro_rules! either_many {
                     use Matches::*;
                     $crate::either_many!(@__first $($varn),+)
                 };
+
+                const LAST: usize = [$(Matches::$varn),+].len() - 1;
             }
 
             #[allow(non_snake_case)]
-            pub struct [<$name State>] $(< $($lf),*>)? {
+            pub struct [<$name State>] $($gen)* {
+                __component_count: usize,
                 $($varn: <$($vart)+ as WorldQuery>::State),+
             }
 
-            unsafe impl $(< $($lf),*>)? FetchState for [<$name State>] $(< $($lf),*>)? {
+            impl [<$name State>] $($static_gen)* {
+                /// Returns the variant given to the entities of `archetype`, or [`None`] if none of
+                /// the variants match it.
+                pub fn archetype_kind(&self, archetype: &Archetype) -> Option<[<$name Kind>]> {
+                    $(if self.$varn.matches_archetype(archetype) {
+                        Some([<$name Kind>]::$varn)
+                    })else+ else {
+                        None
+                    }
+                }
+
+                /// Returns how every archetype in `archetypes` is resolved.
+                pub fn archetype_branches<'__a>(
+                    &'__a self,
+                    archetypes: &'__a Archetypes,
+                ) -> impl Iterator<Item = $crate::ArchetypeBranch<'__a, [<$name Kind>]>> + '__a {
+                    let branches = [$(
+                        $crate::exports::branch_components(self.__component_count, |access| {
+                            self.$varn.update_component_access(access);
+                        }),
+                    )+];
+                    archetypes.iter().map(move |archetype| {
+                        let kind = self.archetype_kind(archetype);
+                        let components = kind.map_or(&[][..], |kind| &branches[kind as usize][..]);
+                        $crate::ArchetypeBranch::new(archetype, kind, components)
+                    })
+                }
+            }
+
+            unsafe impl $($gen)* FetchState for [<$name State>] $($gen)* {
+                #[allow(non_snake_case)]
                 fn init(world: &mut World) -> Self {
+                    $(let $varn = <$($vart)+ as WorldQuery>::State::init(world);)+
                     Self {
-                        $($varn: <$($vart)+ as WorldQuery>::State::init(world)),+
+                        __component_count: world.components().len(),
+                        $($varn),+
                     }
                 }
 
                 fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
-                    $(self.$varn.update_component_access(access);)+
+                    // only one variant is ever fetched for an entity, so they can't conflict
+                    let base = access.clone();
+                    $($crate::exports::add_exclusive_branch_access(
+                        access,
+                        &base,
+                        self.__component_count,
+                        |access| self.$varn.update_component_access(access),
+                    );)+
                 }
             
                 fn update_archetype_component_access(
@@ -79,17 +265,17 @@ macro_rules! either_many {
             }
 
             #[allow(non_snake_case)]
-            pub struct [<$name Fetch>] $(< $($lf),*>)? {
+            pub struct [<$name Fetch>] $($gen)* {
                 matches: Matches,
                 $($varn: <$($vart)+ as WorldQuery>::Fetch),+
             }
 
-            impl $(< $($lf),*>)? Fetch<'_> for [<$name Fetch>] $(< $($lf),*>)? {
-                type Item = $name $(< $($lf),*>)?;
-                type State = [<$name State>] $(< $($lf),*>)?;
+            impl<$fetch_lf> Fetch<$fetch_lf> for [<$name Fetch>] $($static_gen)* {
+                type Item = $name $($gen)*;
+                type State = [<$name State>] $($static_gen)*;
 
                 fn is_dense(&self) -> bool {
-                    $(self.$varn.is_dense())||+
+                    $(self.$varn.is_dense())&&+
                 }
             
                 unsafe fn init(
@@ -115,26 +301,34 @@ macro_rules! either_many {
                     archetype: &Archetype,
                     tables: &Tables,
                 ) {
-                    $(if state.$varn.matches_archetype(archetype) {
+                    $(if ($crate::exports::UNCHECKED && Matches::$varn as usize == Matches::LAST)
+                        || state.$varn.matches_archetype(archetype)
+                    {
                         self.$varn.set_archetype(&state.$varn, archetype, tables);
                         self.matches = {
                             use Matches::*;
                             $varn
                         };
-                    })else+ else if cfg!(debug_assertions) {
-                        unreachable!("None of the variants were matched. At least one should be.");
+                    })else+ else {
+                        $crate::exports::unmatched(format_args!(
+                            "None of the variants were matched. At least one should be."
+                        ));
                     }
                 }
             
                 unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
-                    $(if state.$varn.matches_table(table) {
+                    $(if ($crate::exports::UNCHECKED && Matches::$varn as usize == Matches::LAST)
+                        || state.$varn.matches_table(table)
+                    {
                         self.$varn.set_table(&state.$varn, table);
                         self.matches = {
                             use Matches::*;
                             $varn
                         };
-                    })else+ else if cfg!(debug_assertions) {
-                        unreachable!("None of the variants were matched. At least one should be.");
+                    })else+ else {
+                        $crate::exports::unmatched(format_args!(
+                            "None of the variants were matched. At least one should be."
+                        ));
                     }
                 }
             
@@ -155,10 +349,315 @@ macro_rules! either_many {
                 }
             }
 
-            impl $(< $($lf),*>)? WorldQuery for $name $(< $($lf),*>)? {
-                type Fetch = [<$name Fetch>] $(< $($lf),*>)?;
-                type State = [<$name State>] $(< $($lf),*>)?;
+            unsafe impl<$fetch_lf> $crate::VariantFetch<$fetch_lf> for [<$name Fetch>] $($static_gen)* {
+                const VARIANT_COUNT: usize = [$(Matches::$varn),+].len();
+
+                fn matches_archetype_variant(
+                    state: &Self::State,
+                    variant: usize,
+                    archetype: &Archetype,
+                ) -> bool {
+                    $(variant == Matches::$varn as usize && state.$varn.matches_archetype(archetype))||+
+                }
+
+                fn matches_table_variant(state: &Self::State, variant: usize, table: &Table) -> bool {
+                    $(variant == Matches::$varn as usize && state.$varn.matches_table(table))||+
+                }
+
+                unsafe fn set_archetype_variant(
+                    &mut self,
+                    state: &Self::State,
+                    variant: usize,
+                    archetype: &Archetype,
+                    tables: &Tables,
+                ) {
+                    $(if variant == Matches::$varn as usize {
+                        self.$varn.set_archetype(&state.$varn, archetype, tables);
+                        self.matches = Matches::$varn;
+                    })else+
+                }
+
+                unsafe fn set_table_variant(
+                    &mut self,
+                    state: &Self::State,
+                    variant: usize,
+                    table: &Table,
+                ) {
+                    $(if variant == Matches::$varn as usize {
+                        self.$varn.set_table(&state.$varn, table);
+                        self.matches = Matches::$varn;
+                    })else+
+                }
+            }
+
+            impl $($gen)* WorldQuery for $name $($gen)* {
+                type Fetch = [<$name Fetch>] $($static_gen)*;
+                type State = [<$name State>] $($static_gen)*;
+            }
+
+            impl $($gen)* $crate::VariantQuery for $name $($gen)* {
+                type Kind = [<$name Kind>];
+
+                fn kinds() -> &'static [Self::Kind] {
+                    &[$([<$name Kind>]::$varn),+]
+                }
+
+                fn variant_names() -> &'static [&'static str] {
+                    &[$(stringify!($varn)),+]
+                }
+
+                fn archetype_variant(state: &Self::State, archetype: &Archetype) -> Option<usize> {
+                    state.archetype_kind(archetype).map(|kind| kind as usize)
+                }
+            }
+
+            impl $($gen)* $crate::QueryRequirements for $name $($gen)* {
+                fn requirements() -> $crate::Requirements {
+                    $crate::Requirements::never()
+                        $(.or(&<$($vart)+ as $crate::QueryRequirements>::requirements()))+
+                }
+            }
+
+            impl $($gen)* $crate::Variants for $name $($gen)* {
+                fn variants() -> $crate::exports::Vec<(&'static str, $crate::Requirements)> {
+                    let mut variants = $crate::exports::Vec::new();
+                    $(variants.push((
+                        stringify!($varn),
+                        <$($vart)+ as $crate::QueryRequirements>::requirements(),
+                    ));)+
+                    variants
+                }
             }
         }}
     };
+    (readonly $(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? > as mut $dyn:ty, $($variants:tt)+) => {
+        $crate::either_many!(readonly $(#[$($m),*])? $name<$lf>, $($variants)+);
+        $crate::either_many!(@__dyn $name $lf [$dyn] [mut], $($variants)+);
+    };
+    ($(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? > as mut $dyn:ty, $($variants:tt)+) => {
+        $crate::either_many!($(#[$($m),*])? $name<$lf>, $($variants)+);
+        $crate::either_many!(@__dyn $name $lf [$dyn] [mut], $($variants)+);
+    };
+    (readonly $(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? > as $dyn:ty, $($variants:tt)+) => {
+        $crate::either_many!(readonly $(#[$($m),*])? $name<$lf>, $($variants)+);
+        $crate::either_many!(@__dyn $name $lf [$dyn] [], $($variants)+);
+    };
+    ($(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? > as $dyn:ty, $($variants:tt)+) => {
+        $crate::either_many!($(#[$($m),*])? $name<$lf>, $($variants)+);
+        $crate::either_many!(@__dyn $name $lf [$dyn] [], $($variants)+);
+    };
+    (@__dyn $name:ident $lf:lifetime [$dyn:ty] [$($mut:tt)?], shared($($shared:tt)+), $($variants:tt)+) => {
+        $crate::exports::paste!{
+            $crate::either_many!(@__dyn [<$name Variant>] $lf [$dyn] [$($mut)?], $($variants)+);
+        }
+    };
+    (@__dyn $name:ident $lf:lifetime [$dyn:ty] [], $($varn:ident($($vart:tt)+)),+ $(, )?) => {
+        impl<$lf> $name<$lf> {
+            /// Returns the item of the matched variant as a trait object.
+            pub fn as_dyn(&self) -> &$dyn {
+                match self {
+                    $($name::$varn(item) => &**item,)+
+                }
+            }
+        }
+    };
+    (@__dyn $name:ident $lf:lifetime [$dyn:ty] [mut], $($varn:ident($($vart:tt)+)),+ $(, )?) => {
+        $crate::either_many!(@__dyn $name $lf [$dyn] [], $($varn($($vart)+)),+);
+
+        impl<$lf> $name<$lf> {
+            /// Returns the item of the matched variant as a mutable trait object.
+            pub fn as_dyn_mut(&mut self) -> &mut $dyn {
+                match self {
+                    $($name::$varn(item) => &mut **item,)+
+                }
+            }
+        }
+    };
+    (
+        readonly $(#[$($m:meta),*])? $name:ident $(< $lf:lifetime $(, )? >)?,
+        shared($($shared:tt)+),
+        $($variants:tt)+
+    ) => {
+        $crate::exports::paste!{
+            $crate::either_many!(readonly $(#[$($m),*])? [<$name Variant>] $(<$lf>)?, $($variants)+);
+        }
+        $crate::either_many!(@__shared $name $(<$lf>)?, [$($shared)+], $($variants)+);
+    };
+    (
+        $(#[$($m:meta),*])? $name:ident $(< $lf:lifetime $(, )? >)?,
+        shared($($shared:tt)+),
+        $($variants:tt)+
+    ) => {
+        $crate::exports::paste!{
+            $crate::either_many!($(#[$($m),*])? [<$name Variant>] $(<$lf>)?, $($variants)+);
+        }
+        $crate::either_many!(@__shared $name $(<$lf>)?, [$($shared)+], $($variants)+);
+    };
+    (@__shared $name:ident < $lf:lifetime >, [$($shared:tt)+], $($variants:tt)+) => {
+        $crate::either_many!(@__shared_define $name [<$lf>] [<'static>] $lf $lf, [$($shared)+], $($variants)+);
+    };
+    (@__shared $name:ident, [$($shared:tt)+], $($variants:tt)+) => {
+        $crate::either_many!(@__shared_define $name [] [] 'static '__w, [$($shared)+], $($variants)+);
+    };
+    (@__shared_define
+        $name:ident [$($gen:tt)*] [$($static_gen:tt)*] $item_lf:lifetime $fetch_lf:lifetime,
+        [$shared:ty],
+        $($varn:ident($($vart:tt)+)),+ $(, )?
+    ) => {
+        $crate::exports::paste!{
+            #[doc =
+                "A [world query](::bevy::ecs::query::WorldQuery) matching the world query shared "
+                "by all variants, along with a [`" $name "Variant`]."
+            ]
+            pub type $name $($gen)* = ($shared, [<$name Variant>] $($gen)*);
+
+            #[doc =
+                "Gets a specific variant of an entity's item, along with its shared item, in a "
+                "[`Query`](::bevy::ecs::system::Query) over [`" $name "`]."
+            ]
+            pub trait [<$name QueryExt>] {$(
+                #[doc =
+                    "Gets the entity's shared item and [`" $name "Variant::" $varn "`] item."
+                ]
+                #[allow(clippy::mut_from_ref)]
+                fn [<get_ $varn:snake>]<$fetch_lf>(
+                    &$fetch_lf self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<(
+                    <
+                        <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$fetch_lf>
+                    >::Item,
+                    <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item,
+                ), $crate::EitherQueryError>
+                where
+                    Self: $crate::exports::ReadOnlyGet<$fetch_lf, Item = (
+                        <
+                            <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                            as ::bevy::ecs::query::Fetch<$fetch_lf>
+                        >::Item,
+                        [<__ $name:lower variant>]::Item<$fetch_lf>,
+                    )>;
+
+                #[doc =
+                    "Gets the entity's shared item and [`" $name "Variant::" $varn "`] item "
+                    "mutably."
+                ]
+                fn [<get_ $varn:snake _mut>]<$fetch_lf>(
+                    &$fetch_lf mut self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<(
+                    <
+                        <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$fetch_lf>
+                    >::Item,
+                    <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item,
+                ), $crate::EitherQueryError>;
+            )+}
+
+            impl<'__q, __Q, __F> [<$name QueryExt>] for ::bevy::ecs::system::Query<'__q, __Q, __F>
+            where
+                __Q: ::bevy::ecs::query::WorldQuery<Fetch = (
+                    <$shared as ::bevy::ecs::query::WorldQuery>::Fetch,
+                    [<__ $name:lower variant>]::[<$name VariantFetch>] $($static_gen)*,
+                )>,
+                __F: ::bevy::ecs::query::WorldQuery,
+                __F::Fetch: ::bevy::ecs::query::FilterFetch,
+            {$(
+                #[allow(clippy::mut_from_ref)]
+                fn [<get_ $varn:snake>]<$fetch_lf>(
+                    &$fetch_lf self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<(
+                    <
+                        <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$fetch_lf>
+                    >::Item,
+                    <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item,
+                ), $crate::EitherQueryError>
+                where
+                    Self: $crate::exports::ReadOnlyGet<$fetch_lf, Item = (
+                        <
+                            <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                            as ::bevy::ecs::query::Fetch<$fetch_lf>
+                        >::Item,
+                        [<__ $name:lower variant>]::Item<$fetch_lf>,
+                    )>,
+                {
+                    let (shared, variant) = $crate::exports::ReadOnlyGet::get_read_only(self, entity)?;
+                    variant.[<into_ $varn:snake>]()
+                        .map(|item| (shared, item))
+                        .map_err(|other| $crate::EitherQueryError::OtherVariant(other.variant_name()))
+                }
+
+                fn [<get_ $varn:snake _mut>]<$fetch_lf>(
+                    &$fetch_lf mut self,
+                    entity: ::bevy::ecs::entity::Entity,
+                ) -> Result<(
+                    <
+                        <$shared as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$fetch_lf>
+                    >::Item,
+                    <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<$item_lf>
+                    >::Item,
+                ), $crate::EitherQueryError> {
+                    let (shared, variant) = self.get_mut(entity)?;
+                    variant.[<into_ $varn:snake>]()
+                        .map(|item| (shared, item))
+                        .map_err(|other| $crate::EitherQueryError::OtherVariant(other.variant_name()))
+                }
+            )+}
+        }
+    };
+    (readonly $(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? >, $($variants:tt)+) => {
+        $crate::either_many!($(#[$($m),*])? $name<$lf>, $($variants)+);
+
+        $crate::exports::paste!{
+            unsafe impl ::bevy::ecs::query::ReadOnlyFetch for [<__ $name:lower>]::[<$name Fetch>]<'static> {}
+        }
+    };
+    (readonly $(#[$($m:meta),*])? $name:ident, $($variants:tt)+) => {
+        $crate::either_many!($(#[$($m),*])? $name, $($variants)+);
+
+        $crate::exports::paste!{
+            unsafe impl ::bevy::ecs::query::ReadOnlyFetch for [<__ $name:lower>]::[<$name Fetch>] {}
+        }
+    };
+    ($(#[$($m:meta),*])? $name:ident < $lf:lifetime $(, )? >, $($variants:tt)+) => {
+        $crate::either_many!(@__define $(#[$($m),*])? $name [<$lf>] [<'static>] $lf $lf, $($variants)+);
+    };
+    ($(#[$($m:meta),*])? $name:ident, $($variants:tt)+) => {
+        $crate::either_many!(@__define $(#[$($m),*])? $name [] [] 'static '__w, $($variants)+);
+        $crate::either_many!(@__borrowless $name, $($variants)+);
+    };
+    (@__borrowless $name:ident, $($varn:ident($($vart:tt)+)),+ $(, )?) => {
+        // without a lifetime, the items are built from `Fetch<'static>`, so no variant may borrow
+        // from the world
+        const _: () = {
+            fn requires_item<'__w, F: ::bevy::ecs::query::Fetch<'__w, Item = I>, I>() {}
+
+            #[allow(dead_code)]
+            fn borrowless<'__w>() {$(
+                requires_item::<
+                    '__w,
+                    <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch,
+                    <
+                        <$($vart)+ as ::bevy::ecs::query::WorldQuery>::Fetch
+                        as ::bevy::ecs::query::Fetch<'static>
+                    >::Item,
+                >();
+            )+}
+        };
+    };
 }
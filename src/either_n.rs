@@ -0,0 +1,579 @@
+use super::{*, access::{add_exclusive_branch_access, branch_components}, config::VariantFetch, shadow::*};
+use alloc::vec::Vec;
+
+/// Marks a [flattening](Flatten3) of nested [`Either`]s in their second type, like
+/// `Either<A, Either<B, C>>`, as given by [`either!`](crate::either).
+pub enum RightNested {}
+
+/// Marks a [flattening](Flatten3) of nested [`Either`]s in their first type, like
+/// `Either<Either<A, B>, C>`.
+pub enum LeftNested {}
+
+/// The variant matched by an N-ary fetch, shared by every arity.
+#[derive(Clone, Copy)]
+enum Matches {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+}
+
+/// Wraps `$x` in [`Either`] variants, outermost first. `@lefts` first wraps `$x` in a `Left` for
+/// each of the given identifiers.
+macro_rules! nesting {
+    ([] $x:ident) => {
+        $x
+    };
+    ([$first:ident $($rest:ident)*] $x:ident) => {
+        Either::$first(nesting!([$($rest)*] $x))
+    };
+    (@lefts [] [$($path:ident)*] $x:ident) => {
+        nesting!([$($path)*] $x)
+    };
+    (@lefts [$first:ident $($rest:ident)*] [$($path:ident)*] $x:ident) => {
+        Either::Left(nesting!(@lefts [$($rest)*] [$($path)*] $x))
+    };
+}
+
+/// A left-nested [`Either`] over the given types.
+macro_rules! left_nested {
+    ([$nested:ty]) => {
+        $nested
+    };
+    ([$nested:ty] $next:ident $($rest:ident)*) => {
+        left_nested!([Either<$nested, $next>] $($rest)*)
+    };
+}
+
+/// Implements a `Flatten` trait by first finding the [nesting] of every variant.
+macro_rules! impl_flatten {
+    (@right $n:literal [$($done:tt)*] [$($path:ident)*] $last:ident) => {
+        impl_flatten!(@emit $n RightNested, [$($done)* {[$($path)*]} $last]);
+    };
+    (@right $n:literal [$($done:tt)*] [$($path:ident)*] $var:ident $($rest:ident)+) => {
+        impl_flatten!(@right $n [$($done)* {[$($path)* Left]} $var] [$($path)* Right] $($rest)+);
+    };
+    (@left $n:literal [$($done:tt)*] $var:ident) => {
+        impl_flatten!(@emit $n LeftNested, [$($done)* {[Right]} $var]);
+    };
+    (@left $n:literal [$($done:tt)*] $var:ident $($rest:ident)+) => {
+        impl_flatten!(@left $n [$($done)* {@lefts [$($rest)*] [Right]} $var] $($rest)+);
+    };
+    (@emit $n:literal $nesting:ident, [$({$($path:tt)*} $var:ident)+]) => { paste::paste! {
+        impl<$($var),+> [<Flatten $n>]<$nesting> for impl_flatten!(@nested $nesting $($var)+) {
+            type Flat = [<Either $n>]<$($var),+>;
+
+            fn [<flatten $n>](self) -> Self::Flat {
+                match self {
+                    $(nesting!($($path)* x) => [<Either $n>]::$var(x),)+
+                }
+            }
+
+            fn [<unflatten $n>](flat: Self::Flat) -> Self {
+                match flat {
+                    $([<Either $n>]::$var(x) => nesting!($($path)* x),)+
+                }
+            }
+        }
+    }};
+    (@nested RightNested $($var:ident)+) => {
+        crate::either![$($var),+]
+    };
+    (@nested LeftNested $first:ident $($var:ident)+) => {
+        left_nested!([$first] $($var)+)
+    };
+    ($n:literal $first:ident $($var:ident)+) => {
+        impl_flatten!(@right $n [] [] $first $($var)+);
+        impl_flatten!(@left $n [{@lefts [$($var)+] []} $first] $($var)+);
+    };
+}
+
+fn split_both<T, U>(both: Option<EitherBoth<T, U>>) -> (Option<T>, Option<U>) {
+    match both {
+        Some(EitherBoth::Left(t)) => (Some(t), None),
+        Some(EitherBoth::Right(u)) => (None, Some(u)),
+        Some(EitherBoth::Both(t, u)) => (Some(t), Some(u)),
+        None => (None, None),
+    }
+}
+
+fn join_both<T, U>(t: Option<T>, u: Option<U>) -> Option<EitherBoth<T, U>> {
+    match (t, u) {
+        (Some(t), None) => Some(EitherBoth::Left(t)),
+        (None, Some(u)) => Some(EitherBoth::Right(u)),
+        (Some(t), Some(u)) => Some(EitherBoth::Both(t, u)),
+        (None, None) => None,
+    }
+}
+
+/// Defines a `FlattenBoth` trait and implements it for both nestings, binding each type's item to a variable of the
+/// same name.
+macro_rules! impl_flatten_both {
+    (@right_type $last:ident) => {
+        $last
+    };
+    (@right_type $first:ident $($rest:ident)+) => {
+        EitherBoth<$first, impl_flatten_both!(@right_type $($rest)+)>
+    };
+    (@left_type [$nested:ty]) => {
+        $nested
+    };
+    (@left_type [$nested:ty] $next:ident $($rest:ident)*) => {
+        impl_flatten_both!(@left_type [EitherBoth<$nested, $next>] $($rest)*)
+    };
+    (@right_split $nested:ident $last:ident) => {
+        let $last = $nested;
+    };
+    (@right_split $nested:ident $first:ident $($rest:ident)+) => {
+        let ($first, $nested) = split_both($nested);
+        impl_flatten_both!(@right_split $nested $($rest)+);
+    };
+    // the types are given last first, as the outermost `EitherBoth` holds the last one
+    (@left_split $nested:ident [$($reversed:ident)*] $first:ident $($rest:ident)*) => {
+        impl_flatten_both!(@left_split $nested [$first $($reversed)*] $($rest)*);
+    };
+    (@left_split $nested:ident [$first:ident]) => {
+        let $first = $nested;
+    };
+    (@left_split $nested:ident [$last:ident $($reversed:ident)+]) => {
+        let ($nested, $last) = split_both($nested);
+        impl_flatten_both!(@left_split $nested [$($reversed)+]);
+    };
+    (@right_join $last:ident) => {
+        $last
+    };
+    (@right_join $first:ident $($rest:ident)+) => {
+        join_both($first, impl_flatten_both!(@right_join $($rest)+))
+    };
+    (@left_join [$nested:expr]) => {
+        $nested
+    };
+    (@left_join [$nested:expr] $next:ident $($rest:ident)*) => {
+        impl_flatten_both!(@left_join [join_both($nested, $next)] $($rest)*)
+    };
+    ($n:literal $first:ident $($var:ident)+) => { paste::paste! {
+        #[doc = "Converts nested [`EitherBoth`]s over " $n " types into a tuple with an [`Option`] of"]
+        /// each type, and back.
+        ///
+        /// Like with the `Flatten` traits, `Nesting` is either [`RightNested`] or [`LeftNested`].
+        pub trait [<FlattenBoth $n>]<Nesting>: Sized {
+            type Flat;
+
+            /// Converts the nested [`EitherBoth`]s into the tuple, in which at least one item is
+            /// [`Some`].
+            fn [<flatten_both $n>](self) -> Self::Flat;
+
+            /// Converts the tuple into the nested [`EitherBoth`]s, or gives [`None`] if every item
+            /// is [`None`].
+            fn [<unflatten_both $n>](flat: Self::Flat) -> Option<Self>;
+        }
+
+        #[allow(non_snake_case)]
+        impl<$first, $($var),+> [<FlattenBoth $n>]<RightNested>
+            for impl_flatten_both!(@right_type $first $($var)+)
+        {
+            type Flat = (Option<$first>, $(Option<$var>),+);
+
+            fn [<flatten_both $n>](self) -> Self::Flat {
+                let nested = Some(self);
+                impl_flatten_both!(@right_split nested $first $($var)+);
+                ($first, $($var),+)
+            }
+
+            fn [<unflatten_both $n>](($first, $($var),+): Self::Flat) -> Option<Self> {
+                impl_flatten_both!(@right_join $first $($var)+)
+            }
+        }
+
+        #[allow(non_snake_case)]
+        impl<$first, $($var),+> [<FlattenBoth $n>]<LeftNested>
+            for impl_flatten_both!(@left_type [$first] $($var)+)
+        {
+            type Flat = (Option<$first>, $(Option<$var>),+);
+
+            fn [<flatten_both $n>](self) -> Self::Flat {
+                let nested = Some(self);
+                impl_flatten_both!(@left_split nested [] $first $($var)+);
+                ($first, $($var),+)
+            }
+
+            fn [<unflatten_both $n>](($first, $($var),+): Self::Flat) -> Option<Self> {
+                impl_flatten_both!(@left_join [$first] $($var)+)
+            }
+        }
+    }};
+}
+
+impl_flatten_both!(3 A B C);
+impl_flatten_both!(4 A B C D);
+impl_flatten_both!(5 A B C D E);
+impl_flatten_both!(6 A B C D E F);
+impl_flatten_both!(7 A B C D E F G);
+impl_flatten_both!(8 A B C D E F G H);
+
+macro_rules! impl_either_n {
+    ($n:literal, $($var:ident),+) => { paste::paste! {
+        #[doc = "A type that contains one of " $n " types, or a [world query](WorldQuery) giving the"]
+        #[doc = "item of the first of " $n " queries that matches."]
+        ///
+        /// Like with [`Either`], the queries may access the same components in conflicting ways.
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+        pub enum [<Either $n>]<$($var),+> {
+            $($var($var)),+
+        }
+
+        #[doc = "The variant of an [`Either" $n "`], without its item."]
+        #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
+        pub enum [<Either $n Kind>] {
+            $($var),+
+        }
+
+        impl<$($var),+> [<Either $n>]<$($var),+> {
+            /// Returns which variant this is.
+            pub fn kind(&self) -> [<Either $n Kind>] {
+                match self {
+                    $([<Either $n>]::$var(_) => [<Either $n Kind>]::$var),+
+                }
+            }
+        }
+
+        #[doc = "Converts nested [`Either`]s over " $n " types into an [`Either" $n "`] and back."]
+        ///
+        /// `Nesting` is either [`RightNested`] or [`LeftNested`]. It is usually inferred, but has
+        /// to be given when both nestings apply, like with `Either<Either<A, B>, Either<C, D>>`.
+        pub trait [<Flatten $n>]<Nesting> {
+            type Flat;
+
+            /// Converts the nested [`Either`]s into the flat enum.
+            fn [<flatten $n>](self) -> Self::Flat;
+
+            /// Converts the flat enum into the nested [`Either`]s.
+            fn [<unflatten $n>](flat: Self::Flat) -> Self;
+        }
+
+        impl_flatten!($n $($var)+);
+
+        #[allow(non_snake_case)]
+        pub struct [<Either $n State>]<$($var: FetchState),+> {
+            $($var: $var,)+
+            component_count: usize,
+        }
+
+        impl<$($var: FetchState),+> [<Either $n State>]<$($var),+> {
+            /// Returns the variant given to the entities of `archetype`, or [`None`] if none of
+            /// the queries match it.
+            pub fn archetype_kind(&self, archetype: &Archetype) -> Option<[<Either $n Kind>]> {
+                $(if self.$var.matches_archetype(archetype) {
+                    Some([<Either $n Kind>]::$var)
+                })else+ else {
+                    None
+                }
+            }
+
+            /// Returns how every archetype in `archetypes` is resolved.
+            pub fn archetype_branches<'a>(
+                &'a self,
+                archetypes: &'a Archetypes,
+            ) -> impl Iterator<Item = ArchetypeBranch<'a, [<Either $n Kind>]>> + 'a {
+                let branches = [$(
+                    branch_components(self.component_count, |access| {
+                        self.$var.update_component_access(access);
+                    }),
+                )+];
+                archetypes.iter().map(move |archetype| {
+                    let kind = self.archetype_kind(archetype);
+                    let components = kind.map_or(&[][..], |kind| &branches[kind as usize][..]);
+                    ArchetypeBranch::new(archetype, kind, components)
+                })
+            }
+        }
+
+        unsafe impl<$($var: FetchState),+> FetchState for [<Either $n State>]<$($var),+> {
+            #[allow(non_snake_case)]
+            fn init(world: &mut World) -> Self {
+                $(let $var = $var::init(world);)+
+                [<Either $n State>] {
+                    $($var,)+
+                    component_count: world.components().len(),
+                }
+            }
+
+            fn update_component_access(&self, access: &mut FilteredAccess<ComponentId>) {
+                // only one query is ever fetched for an entity, so they can't conflict
+                let base = access.clone();
+                $(add_exclusive_branch_access(access, &base, self.component_count, |access| {
+                    self.$var.update_component_access(access);
+                });)+
+            }
+
+            fn update_archetype_component_access(
+                &self,
+                archetype: &Archetype,
+                access: &mut Access<ArchetypeComponentId>,
+            ) {
+                $(self.$var.update_archetype_component_access(archetype, access);)+
+            }
+
+            fn matches_archetype(&self, archetype: &Archetype) -> bool {
+                $(self.$var.matches_archetype(archetype))||+
+            }
+
+            fn matches_table(&self, table: &Table) -> bool {
+                $(self.$var.matches_table(table))||+
+            }
+        }
+
+        #[allow(non_snake_case)]
+        pub struct [<Either $n Fetch>]<$($var),+> {
+            $($var: $var,)+
+            matches: Matches,
+        }
+
+        impl<'w, $($var: Fetch<'w>),+> Fetch<'w> for [<Either $n Fetch>]<$($var),+> {
+            type Item = [<Either $n>]<$($var::Item),+>;
+            type State = [<Either $n State>]<$($var::State),+>;
+
+            fn is_dense(&self) -> bool {
+                $(self.$var.is_dense())&&+
+            }
+
+            unsafe fn init(
+                world: &World,
+                state: &Self::State,
+                last_change_tick: u32,
+                change_tick: u32,
+            ) -> Self {
+                [<Either $n Fetch>] {
+                    $($var: $var::init(world, &state.$var, last_change_tick, change_tick),)+
+                    matches: Matches::A,
+                }
+            }
+
+            unsafe fn set_archetype(
+                &mut self,
+                state: &Self::State,
+                archetype: &Archetype,
+                tables: &Tables,
+            ) {
+                $(if (UNCHECKED && Matches::$var as usize == $n - 1)
+                    || state.$var.matches_archetype(archetype)
+                {
+                    self.$var.set_archetype(&state.$var, archetype, tables);
+                    self.matches = Matches::$var;
+                })else+ else {
+                    unmatched(format_args!(
+                        "None of the variants were matched. At least one should be."
+                    ));
+                }
+            }
+
+            unsafe fn set_table(&mut self, state: &Self::State, table: &Table) {
+                $(if (UNCHECKED && Matches::$var as usize == $n - 1)
+                    || state.$var.matches_table(table)
+                {
+                    self.$var.set_table(&state.$var, table);
+                    self.matches = Matches::$var;
+                })else+ else {
+                    unmatched(format_args!(
+                        "None of the variants were matched. At least one should be."
+                    ));
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            unsafe fn archetype_fetch(&mut self, archetype_index: usize) -> Self::Item {
+                match self.matches {
+                    $(Matches::$var => [<Either $n>]::$var(self.$var.archetype_fetch(archetype_index)),)+
+                    _ => unreachable!("the matched variant is always set by this fetch"),
+                }
+            }
+
+            #[allow(unreachable_patterns)]
+            unsafe fn table_fetch(&mut self, table_row: usize) -> Self::Item {
+                match self.matches {
+                    $(Matches::$var => [<Either $n>]::$var(self.$var.table_fetch(table_row)),)+
+                    _ => unreachable!("the matched variant is always set by this fetch"),
+                }
+            }
+        }
+
+        unsafe impl<'w, $($var: Fetch<'w>),+> VariantFetch<'w> for [<Either $n Fetch>]<$($var),+> {
+            const VARIANT_COUNT: usize = $n;
+
+            fn matches_archetype_variant(
+                state: &Self::State,
+                variant: usize,
+                archetype: &Archetype,
+            ) -> bool {
+                $(variant == Matches::$var as usize && state.$var.matches_archetype(archetype))||+
+            }
+
+            fn matches_table_variant(state: &Self::State, variant: usize, table: &Table) -> bool {
+                $(variant == Matches::$var as usize && state.$var.matches_table(table))||+
+            }
+
+            unsafe fn set_archetype_variant(
+                &mut self,
+                state: &Self::State,
+                variant: usize,
+                archetype: &Archetype,
+                tables: &Tables,
+            ) {
+                $(if variant == Matches::$var as usize {
+                    self.$var.set_archetype(&state.$var, archetype, tables);
+                    self.matches = Matches::$var;
+                })else+
+            }
+
+            unsafe fn set_table_variant(
+                &mut self,
+                state: &Self::State,
+                variant: usize,
+                table: &Table,
+            ) {
+                $(if variant == Matches::$var as usize {
+                    self.$var.set_table(&state.$var, table);
+                    self.matches = Matches::$var;
+                })else+
+            }
+        }
+
+        unsafe impl<$($var: ReadOnlyFetch),+> ReadOnlyFetch for [<Either $n Fetch>]<$($var),+> {}
+
+        impl<$($var: WorldQuery),+> WorldQuery for [<Either $n>]<$($var),+> {
+            type Fetch = [<Either $n Fetch>]<$($var::Fetch),+>;
+            type State = [<Either $n State>]<$($var::State),+>;
+        }
+
+        impl<$($var: WorldQuery),+> VariantQuery for [<Either $n>]<$($var),+> {
+            type Kind = [<Either $n Kind>];
+
+            fn kinds() -> &'static [Self::Kind] {
+                &[$([<Either $n Kind>]::$var),+]
+            }
+
+            fn variant_names() -> &'static [&'static str] {
+                &[$(stringify!($var)),+]
+            }
+
+            fn archetype_variant(state: &Self::State, archetype: &Archetype) -> Option<usize> {
+                state.archetype_kind(archetype).map(|kind| kind as usize)
+            }
+        }
+
+        impl<$($var: QueryRequirements),+> QueryRequirements for [<Either $n>]<$($var),+> {
+            fn requirements() -> Requirements {
+                Requirements::never()
+                    $(.or(&$var::requirements()))+
+            }
+        }
+
+        impl<$($var: QueryRequirements),+> Variants for [<Either $n>]<$($var),+> {
+            fn variants() -> Vec<(&'static str, Requirements)> {
+                alloc::vec![$((stringify!($var), $var::requirements())),+]
+            }
+        }
+    }};
+}
+
+impl_either_n!(3, A, B, C);
+impl_either_n!(4, A, B, C, D);
+impl_either_n!(5, A, B, C, D, E);
+impl_either_n!(6, A, B, C, D, E, F);
+impl_either_n!(7, A, B, C, D, E, F, G);
+impl_either_n!(8, A, B, C, D, E, F, G, H);
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct A(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct B(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct C(u32);
+
+    #[test]
+    fn test_flatten() {
+        let right: crate::either![u8, u16, u32] = Either::Right(Either::Left(2));
+        assert_eq!(right.flatten3(), Either3::B(2));
+        type Right4 = crate::either![u8, u16, u32, u64];
+        let right = Right4::unflatten4(Either4::D(3));
+        assert_eq!(right, Either::Right(Either::Right(Either::Right(3))));
+        assert_eq!(right.flatten4(), Either4::D(3));
+
+        let left: Either<Either<u8, u16>, u32> = Either::Left(Either::Right(1));
+        assert_eq!(left.flatten3(), Either3::B(1));
+        type Left4 = Either<Either<Either<u8, u16>, u32>, u64>;
+        let left = Left4::unflatten4(Either4::A(0));
+        assert_eq!(left, Either::Left(Either::Left(Either::Left(0))));
+        assert_eq!(left.flatten4(), Either4::A(0));
+        assert_eq!(Left4::unflatten4(Either4::C(2)), Either::Left(Either::Right(2)));
+
+        let both: Either<Either<u8, u16>, Either<u32, u64>> = Either::Right(Either::Left(2));
+        assert_eq!(Flatten3::<RightNested>::flatten3(both), Either3::B(2));
+        assert_eq!(Flatten3::<LeftNested>::flatten3(both), Either3::C(Either::Left(2)));
+    }
+
+    #[test]
+    fn test_flatten_both() {
+        let right: EitherBoth<u8, EitherBoth<u16, u32>> = EitherBoth::Both(0, EitherBoth::Right(2));
+        assert_eq!(right.flatten_both3(), (Some(0), None, Some(2)));
+        type Right4 = EitherBoth<u8, EitherBoth<u16, EitherBoth<u32, u64>>>;
+        assert_eq!(
+            Right4::unflatten_both4((None, Some(1), Some(2), None)),
+            Some(EitherBoth::Right(EitherBoth::Both(1, EitherBoth::Left(2)))),
+        );
+        assert_eq!(Right4::unflatten_both4((None, None, None, None)), None);
+
+        let left: EitherBoth<EitherBoth<u8, u16>, u32> = EitherBoth::Left(EitherBoth::Both(0, 1));
+        assert_eq!(left.flatten_both3(), (Some(0), Some(1), None));
+        type Left4 = EitherBoth<EitherBoth<EitherBoth<u8, u16>, u32>, u64>;
+        let left = Left4::unflatten_both4((Some(0), None, Some(2), Some(3))).unwrap();
+        assert_eq!(left, EitherBoth::Both(EitherBoth::Both(EitherBoth::Left(0), 2), 3));
+        assert_eq!(left.flatten_both4(), (Some(0), None, Some(2), Some(3)));
+    }
+
+    #[test]
+    fn test_either_n_query() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<C>(StorageType::SparseSet)).unwrap();
+        let a = world.spawn().insert(A(1)).insert(B(2)).id();
+        let b = world.spawn().insert(B(3)).insert(C(4)).id();
+        let c = world.spawn().insert(C(5)).id();
+        world.spawn();
+
+        let mut query = world.query::<(Entity, Either3<&mut A, &mut B, (&C, Option<&B>)>)>();
+        let mut matched = 0;
+        for (entity, item) in query.iter_mut(&mut world) {
+            match item {
+                Either3::A(mut a_item) => {
+                    assert_eq!(entity, a);
+                    a_item.0 += 10;
+                },
+                Either3::B(mut b_item) => {
+                    assert_eq!(entity, b);
+                    b_item.0 += 10;
+                },
+                Either3::C((c_item, b_item)) => {
+                    assert_eq!(entity, c);
+                    assert_eq!((c_item, b_item), (&C(5), None));
+                },
+            }
+            matched += 1;
+        }
+        assert_eq!(matched, 3);
+        assert_eq!(world.get::<A>(a), Some(&A(11)));
+        assert_eq!(world.get::<B>(a), Some(&B(2)));
+        assert_eq!(world.get::<B>(b), Some(&B(13)));
+        assert!(shadowed_variants::<Either4<&A, &B, &C, (&A, &C)>>() == ["D"]);
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+use core::marker::PhantomData;
+use bevy::app::{AppBuilder, CoreStage, Plugin};
+use bevy::utils::HashMap;
+
+/// An [event](bevy::app::Events) sent when `entity` starts matching `Q`, given the variant
+/// `kind`.
+pub struct EitherEntered<Q: VariantQuery> {
+    pub entity: Entity,
+    pub kind: Q::Kind,
+}
+
+/// An [event](bevy::app::Events) sent when `entity` stops matching `Q`, having last been given
+/// the variant `kind`. This includes `entity` being despawned.
+pub struct EitherExited<Q: VariantQuery> {
+    pub entity: Entity,
+    pub kind: Q::Kind,
+}
+
+/// A [plugin](Plugin) sending [`EitherEntered<Q>`] and [`EitherExited<Q>`] events as entities
+/// start and stop matching `Q`.
+///
+/// The matched entities are compared with the ones of the previous frame in
+/// [`CoreStage::PostUpdate`], so entities matching for less than a frame are missed. An entity
+/// changing from one variant to another still matches `Q`, so it sends neither event.
+pub struct EitherEventsPlugin<Q>(PhantomData<fn() -> Q>);
+
+impl<Q> Default for EitherEventsPlugin<Q> {
+    fn default() -> Self {
+        EitherEventsPlugin(PhantomData)
+    }
+}
+
+impl<Q: VariantQuery + 'static> Plugin for EitherEventsPlugin<Q> {
+    fn build(&self, app: &mut AppBuilder) {
+        app.add_event::<EitherEntered<Q>>()
+            .add_event::<EitherExited<Q>>()
+            .add_system_to_stage(CoreStage::PostUpdate, send_either_events::<Q>.system());
+    }
+}
+
+fn send_either_events<Q: VariantQuery + 'static>(
+    mut variants: Local<VariantArchetypes<Q>>,
+    mut matched: Local<HashMap<Entity, Q::Kind>>,
+    archetypes: &Archetypes,
+    mut entered: EventWriter<EitherEntered<Q>>,
+    mut exited: EventWriter<EitherExited<Q>>,
+) {
+    variants.update(archetypes);
+    let mut current = HashMap::default();
+    for &kind in Q::kinds() {
+        for entity in variants.entities(archetypes, kind) {
+            if !matched.contains_key(&entity) {
+                entered.send(EitherEntered { entity, kind });
+            }
+            current.insert(entity, kind);
+        }
+    }
+    for (&entity, &kind) in matched.iter() {
+        if !current.contains_key(&entity) {
+            exited.send(EitherExited { entity, kind });
+        }
+    }
+    *matched = current;
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use bevy::app::{Events, ManualEventReader};
+    use alloc::vec::Vec;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    type Q = Either<&'static A, &'static B>;
+
+    fn read<T: Component, U>(
+        app: &mut AppBuilder,
+        reader: &mut ManualEventReader<T>,
+        f: impl Fn(&T) -> U,
+    ) -> Vec<U> {
+        let events = app.world().get_resource::<Events<T>>().unwrap();
+        reader.iter(events).map(f).collect()
+    }
+
+    #[test]
+    fn test_either_events() {
+        let mut app = App::build();
+        app.add_plugin(EitherEventsPlugin::<Q>::default());
+        let mut entered = ManualEventReader::<EitherEntered<Q>>::default();
+        let mut exited = ManualEventReader::<EitherExited<Q>>::default();
+
+        let a = app.world_mut().spawn().insert(A).id();
+        let none = app.world_mut().spawn().id();
+        app.app.update();
+        assert_eq!(read(&mut app, &mut entered, |e| (e.entity, e.kind)), [(a, EitherKind::Left)]);
+
+        app.world_mut().entity_mut(a).insert(B).remove::<A>();
+        app.world_mut().entity_mut(none).insert(B);
+        app.app.update();
+        assert_eq!(
+            read(&mut app, &mut entered, |e| (e.entity, e.kind)),
+            [(none, EitherKind::Right)],
+        );
+        assert!(read(&mut app, &mut exited, |e| e.entity).is_empty());
+
+        app.world_mut().despawn(a);
+        app.world_mut().entity_mut(none).remove::<B>();
+        app.app.update();
+        let mut left = read(&mut app, &mut exited, |e| (e.entity, e.kind));
+        left.sort();
+        assert_eq!(left, [(a, EitherKind::Right), (none, EitherKind::Right)]);
+    }
+}
@@ -0,0 +1,192 @@
+use super::*;
+use core::fmt;
+use bevy::ecs::entity::Entity;
+
+/// An error from getting a specific variant of an entity's item, like with
+/// [`EitherQueryExt::get_left`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum EitherQueryError {
+    /// The entity doesn't exist.
+    NoSuchEntity,
+    /// The entity doesn't match the query at all.
+    QueryDoesNotMatch,
+    /// The entity matches the query, but as the named variant.
+    OtherVariant(&'static str),
+}
+
+impl fmt::Display for EitherQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EitherQueryError::NoSuchEntity => f.write_str("The requested entity does not exist."),
+            EitherQueryError::QueryDoesNotMatch => {
+                f.write_str("The given entity does not match the query.")
+            },
+            EitherQueryError::OtherVariant(variant) => {
+                write!(f, "The given entity matches the {} variant instead.", variant)
+            },
+        }
+    }
+}
+
+impl From<QueryEntityError> for EitherQueryError {
+    fn from(error: QueryEntityError) -> Self {
+        match error {
+            QueryEntityError::NoSuchEntity => EitherQueryError::NoSuchEntity,
+            QueryEntityError::QueryDoesNotMatch => EitherQueryError::QueryDoesNotMatch,
+        }
+    }
+}
+
+/// [`Query::get`] for read only queries, so it can be required by methods of traits implemented
+/// for all queries.
+#[doc(hidden)]
+pub trait ReadOnlyGet<'s> {
+    type Item;
+
+    fn get_read_only(&'s self, entity: Entity) -> Result<Self::Item, QueryEntityError>;
+}
+
+impl<'s, 'w, Q, F> ReadOnlyGet<'s> for Query<'w, Q, F>
+where
+    Q: WorldQuery,
+    F: WorldQuery,
+    Q::Fetch: ReadOnlyFetch,
+    F::Fetch: FilterFetch,
+{
+    type Item = <Q::Fetch as Fetch<'s>>::Item;
+
+    fn get_read_only(&'s self, entity: Entity) -> Result<Self::Item, QueryEntityError> {
+        self.get(entity)
+    }
+}
+
+/// Gets a specific side of an entity's item in a [`Query`] over [`Either<T, U>`](Either).
+pub trait EitherQueryExt<T: WorldQuery, U: WorldQuery> {
+    /// Gets the entity's [left](Either::Left) item.
+    fn get_left<'s>(
+        &'s self,
+        entity: Entity,
+    ) -> Result<<T::Fetch as Fetch<'s>>::Item, EitherQueryError>
+    where
+        T::Fetch: ReadOnlyFetch,
+        U::Fetch: ReadOnlyFetch;
+
+    /// Gets the entity's [right](Either::Right) item.
+    fn get_right<'s>(
+        &'s self,
+        entity: Entity,
+    ) -> Result<<U::Fetch as Fetch<'s>>::Item, EitherQueryError>
+    where
+        T::Fetch: ReadOnlyFetch,
+        U::Fetch: ReadOnlyFetch;
+
+    /// Gets the entity's [left](Either::Left) item mutably.
+    fn get_left_mut<'s>(
+        &'s mut self,
+        entity: Entity,
+    ) -> Result<<T::Fetch as Fetch<'s>>::Item, EitherQueryError>;
+
+    /// Gets the entity's [right](Either::Right) item mutably.
+    fn get_right_mut<'s>(
+        &'s mut self,
+        entity: Entity,
+    ) -> Result<<U::Fetch as Fetch<'s>>::Item, EitherQueryError>;
+}
+
+impl<'w, T, U, F> EitherQueryExt<T, U> for Query<'w, Either<T, U>, F>
+where
+    T: WorldQuery,
+    U: WorldQuery,
+    F: WorldQuery,
+    F::Fetch: FilterFetch,
+{
+    fn get_left<'s>(
+        &'s self,
+        entity: Entity,
+    ) -> Result<<T::Fetch as Fetch<'s>>::Item, EitherQueryError>
+    where
+        T::Fetch: ReadOnlyFetch,
+        U::Fetch: ReadOnlyFetch,
+    {
+        match self.get(entity)? {
+            Either::Left(item) => Ok(item),
+            Either::Right(_) => Err(EitherQueryError::OtherVariant("Right")),
+        }
+    }
+
+    fn get_right<'s>(
+        &'s self,
+        entity: Entity,
+    ) -> Result<<U::Fetch as Fetch<'s>>::Item, EitherQueryError>
+    where
+        T::Fetch: ReadOnlyFetch,
+        U::Fetch: ReadOnlyFetch,
+    {
+        match self.get(entity)? {
+            Either::Left(_) => Err(EitherQueryError::OtherVariant("Left")),
+            Either::Right(item) => Ok(item),
+        }
+    }
+
+    fn get_left_mut<'s>(
+        &'s mut self,
+        entity: Entity,
+    ) -> Result<<T::Fetch as Fetch<'s>>::Item, EitherQueryError> {
+        match self.get_mut(entity)? {
+            Either::Left(item) => Ok(item),
+            Either::Right(_) => Err(EitherQueryError::OtherVariant("Right")),
+        }
+    }
+
+    fn get_right_mut<'s>(
+        &'s mut self,
+        entity: Entity,
+    ) -> Result<<U::Fetch as Fetch<'s>>::Item, EitherQueryError> {
+        match self.get_mut(entity)? {
+            Either::Left(_) => Err(EitherQueryError::OtherVariant("Left")),
+            Either::Right(item) => Ok(item),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct A(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct B(u32);
+
+    #[test]
+    fn test_get_side() {
+        let mut world = World::default();
+        let a = world.spawn().insert(A(1)).id();
+        let b = world.spawn().insert(B(2)).id();
+        let none = world.spawn().id();
+        let despawned = world.spawn().id();
+        world.despawn(despawned);
+
+        let mut read_stage = SystemStage::single((move |read: Query<Either<&A, &B>>| {
+            assert_eq!(read.get_left(a), Ok(&A(1)));
+            assert_eq!(read.get_right(b), Ok(&B(2)));
+            assert_eq!(read.get_right(a), Err(EitherQueryError::OtherVariant("Left")));
+            assert_eq!(read.get_left(none), Err(EitherQueryError::QueryDoesNotMatch));
+            assert_eq!(read.get_left(despawned), Err(EitherQueryError::NoSuchEntity));
+        }).system());
+        read_stage.run(&mut world);
+        let mut write_stage = SystemStage::single((move |
+            mut write: Query<Either<&mut A, &mut B>>,
+        | {
+            write.get_right_mut(b).unwrap().0 += 10;
+            assert_eq!(
+                write.get_right_mut(a).map(|b| b.0),
+                Err(EitherQueryError::OtherVariant("Left")),
+            );
+        }).system());
+        write_stage.run(&mut world);
+        assert_eq!(world.get::<B>(b), Some(&B(12)));
+    }
+}
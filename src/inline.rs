@@ -0,0 +1,39 @@
+/// Expands to nested [`Either`](crate::Either)s over the given [world queries], matching the first
+/// one in priority order.
+///
+/// `either![A, B, C]` is `Either<A, Either<B, C>>`, and `either![A]` is just `A`. Use
+/// [`match_either!`](crate::match_either) to match over its items.
+///
+/// [world queries]: bevy::ecs::query::WorldQuery
+#[macro_export]
+macro_rules! either {
+    ($only:ty $(, )?) => {
+        $only
+    };
+    ($first:ty, $($rest:ty),+ $(, )?) => {
+        $crate::Either<$first, $crate::either![$($rest),+]>
+    };
+}
+
+/// Matches over an item of an [`either!`](crate::either) query, with one arm per query in the same
+/// order.
+///
+/// `match_either!(item, a => ..., b => ..., c => ...)` runs the first arm with `a` bound to the
+/// first query's item, the second arm with `b` bound to the second query's item, and so on.
+#[macro_export]
+macro_rules! match_either {
+    ($item:expr, $pat:pat => $arm:expr $(, )?) => {
+        match $item {
+            $pat => $arm,
+        }
+    };
+    ($item:expr, $pat:pat => $arm:expr, $($rest_pat:pat => $rest_arm:expr),+ $(, )?) => {
+        match $item {
+            $crate::Either::Left($pat) => $arm,
+            $crate::Either::Right(rest) => $crate::match_either!(
+                rest,
+                $($rest_pat => $rest_arm),+
+            ),
+        }
+    };
+}
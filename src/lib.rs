@@ -10,6 +10,20 @@
 //! successfully match an entity, then only [`T`'s item](Either::Left) is given, e.g. there isn't a
 //! "both" variant.
 //!
+//! As only one of `T` and `U` is ever fetched for an entity, they may access the same components
+//! in conflicting ways, like in `Either<&mut A, (&A, &B)>`.
+//!
+//! Bevy 0.5 can't record access to a component without also treating the component as a
+//! [`With`] filter, as with its own `Option<&C>`. The access of `Either<&A, &C>` thus looks like
+//! it only reaches entities with both `A` and `C`, so it isn't caught conflicting with a
+//! `Query<&mut C, Without<A>>` in the same system, even though both reach the entities with only
+//! `C`. The same goes for every query here that can skip some of its parts, like
+//! [`EitherBoth`], [`AtLeast`] or the variants of an [`either_many!`](either_many) type. Such
+//! queries should be kept apart with a [`QuerySet`].
+//!
+//! With the [`EitherQueryExt`] trait, queries over [`Either<T, U>`](Either) also get `get_left`,
+//! `get_right`, `get_left_mut` and `get_right_mut` methods.
+//!
 //! ## [`EitherBoth<T, U>`](EitherBoth)
 //!
 //! Similarly to [`Either<T, U>`](Either), [`EitherBoth<T, U>`](EitherBoth) does allow one to match
@@ -22,7 +36,41 @@
 //! This macro creates a new [world query] enum with a new variant for each of its possible matched
 //! [world queries]. There isn't a "both"/"multiple" variant and the priority is always given to the
 //! first declared variant when multiple matches occur. This lets you create [world queries] similar
-//! to [`Either`], matching over one of the variant [world queries] with some priority order.
+//! to [`Either`], matching over one of the variant [world queries] with some priority order. Like
+//! with [`Either`], the variants may access the same components in conflicting ways.
+//!
+//! The new enum takes a single lifetime, like `either_many!(MyEither<'w>, A(&'w A), B(&'w mut B))`,
+//! which is the lifetime of the world borrows in its items. It can be left out when no variant
+//! borrows from the world, and a variant borrowing from the world without it doesn't compile:
+//!
+//! ```compile_fail
+//! # use bevy::prelude::*;
+//! bevy_either::either_many!(Bad, A(&'static Transform), B(Entity));
+//! # fn main() {}
+//! ```
+//!
+//! For each variant, like `MyVariant`, the enum also gets `is_my_variant`, `as_my_variant`,
+//! `as_my_variant_mut`, `into_my_variant`, and `map_my_variant` methods, `variant_name` gives the
+//! matched variant's name, and `kind` gives it as a fieldless `MyEitherKind`. Importing the
+//! generated `MyEitherQueryExt` trait adds `get_my_variant` and `get_my_variant_mut` methods to
+//! queries over `MyEither`, which fail with an [`EitherQueryError`] telling whether the entity is
+//! missing, doesn't match, or is another variant.
+//!
+//! ### `shared`
+//!
+//! When every variant needs the same [world query], it can be declared once with `shared` after
+//! the name, like `either_many!(MyEither<'w>, shared(&'w Transform), A(&'w A), B(&'w B))`. Then
+//! the enum is named `MyEitherVariant`, while `MyEither` is the [world query] of the shared item
+//! along with the variant, `(&'w Transform, MyEitherVariant<'w>)`. The `MyEitherQueryExt` trait's
+//! methods then give the shared item along with the variant's, like `(&Transform, &A)`.
+//!
+//! ### `as dyn`
+//!
+//! When every variant's item dereferences to a type implementing some trait, putting `as dyn Trait`
+//! after the name and lifetime, like `either_many!(MyEither<'w> as dyn Trait, ...)`, adds an
+//! `as_dyn` method giving the matched item as a `&dyn Trait`. With `as mut dyn Trait` instead,
+//! there's also an `as_dyn_mut` method giving a `&mut dyn Trait`, which needs every variant's item
+//! to mutably dereference, like [`Mut`].
 //!
 //! ### `readonly`
 //!
@@ -31,21 +79,215 @@
 //! [read only](ReadOnlyFetch). The type is [read only](ReadOnlyFetch) if and only if all of its
 //! variants are [read only](ReadOnlyFetch), and this is an invariant *you* must uphold.
 //!
+//! ### Shadowed variants
+//!
+//! Since the first matching variant is always given, a variant can be shadowed by higher priority
+//! ones, like `AB` in `either_many!(Q<'a>, A(&'a A), AB((&'a A, &'a B)))`. For [`Either`] and
+//! [`either_many!`](either_many) types, [`shadowed_variants`] finds these variants, while
+//! [`warn_shadowed_variants`] logs them and [`assert_no_shadowed_variants`] panics on them. This
+//! needs every variant to implement [`QueryRequirements`], as the built-in queries and filters do.
+//!
+//! ## [`either!`](either) and [`match_either!`](match_either)
+//!
+//! For one-off queries, [`either![A, B, C]`](either) expands to `Either<A, Either<B, C>>` without
+//! defining a new type, and [`match_either!`](match_either) matches over its items with one arm
+//! per query, like `match_either!(item, a => ..., b => ..., c => ...)`.
+//!
+//! ## [`Either3`] to [`Either8`]
+//!
+//! For more queries without nesting, [`Either3<A, B, C>`](Either3) up to [`Either8`] are
+//! [world queries] giving the item of the first of their queries that matches, as a flat enum with
+//! the variants `A`, `B`, `C` and so on. Nested [`Either`] items can also be converted to and from
+//! these enums with the [`Flatten3`] to [`Flatten8`] traits, like `item.flatten3()`, for both
+//! `Either<A, Either<B, C>>` and `Either<Either<A, B>, C>`. Similarly, the [`FlattenBoth3`] to
+//! [`FlattenBoth8`] traits convert nested [`EitherBoth`] items, like
+//! `EitherBoth<EitherBoth<A, B>, C>`, to and from tuples like `(Option<A>, Option<B>, Option<C>)`.
+//!
+//! ## [`AtLeast<K, Q>`](AtLeast)
+//!
+//! Given a tuple of [world queries] `Q`, [`AtLeast<K, Q>`](AtLeast) matches the entities for which
+//! at least `K` of them match, like `AtLeast<2, (&Weapon, &Armor, &Shield)>`. Its item is a tuple
+//! with an [`Option`] of each query's item.
+//!
+//! ## [`NotQuery<Q>`](NotQuery)
+//!
+//! Generalizing [`Without<T>`] to any [world query], [`NotQuery<Q>`](NotQuery) matches exactly the
+//! archetypes that `Q` doesn't, like `NotQuery<MyEither>` for the entities that aren't any variant
+//! of `MyEither`. It fetches nothing, and is meant to be used as a filter.
+//!
+//! ## [`Configured<Q>`](Configured)
+//!
+//! To change the priority of variants or disable some of them at runtime, query for
+//! [`Configured<Q>`](Configured) over [`Either`], [`EitherBoth`], [`Either3`] to [`Either8`] or
+//! an [`either_many!`](either_many) type. The enabled variants are taken in priority order from
+//! the [`VariantConfig<Q>`](VariantConfig) resource when the query is created, or each time the
+//! system runs with a [`ConfiguredQuery<Q, F>`](ConfiguredQuery) system parameter. Entities only
+//! matching disabled variants are skipped with the [`WithEnabledVariant<Q>`](WithEnabledVariant)
+//! filter. As resources are `'static`, `Q` must be too, like `Configured<MyEither<'static>>`.
+//!
+//! ## [`DynamicEither<M>`](DynamicEither)
+//!
+//! When the matched [world queries] are only known at runtime, [`DynamicEither<M>`](DynamicEither)
+//! matches the first of many [branches](DynamicBranch) of required and excluded components. The
+//! branches are read from the [`DynamicEitherBranches<M>`](DynamicEitherBranches) resource, and
+//! the [item](DynamicEitherItem) gives the matched branch's index and pointers to its required
+//! components.
+//!
+//! ## [`#[derive(EnumComponents)]`](macro@EnumComponents)
+//!
+//! Deriving [`EnumComponents`] for an enum like `enum Ai { Idle, Chase(Target) }` stores each
+//! variant as its own component, `AiIdle` and `AiChase`, so archetypes can be filtered by variant.
+//! It also generates the [`either_many!`](either_many) queries `AiRef<'w>` and `AiMut<'w>` over the
+//! variant components, and [`insert_enum`](InsertEither::insert_enum) inserts a value's variant
+//! component while removing the others.
+//!
+//! ## Iterating by variant
+//!
+//! A [`VariantQueryState<Q, F>`](VariantQueryState) is like a [`QueryState<Q, F>`](QueryState),
+//! but its [`iter_by_variant`](VariantQueryState::iter_by_variant) and
+//! [`iter_by_variant_mut`](VariantQueryState::iter_by_variant_mut) give the items grouped by
+//! variant, in declaration order, like every `Left` item before every `Right` item for [`Either`].
+//! The matched archetypes are kept per variant, so nothing is collected or sorted.
+//!
+//! ## World extensions
+//!
+//! For tests and tools working on a [`World`] directly, the [`EitherWorldExt`] trait adds
+//! [`partition_either::<T, U>()`](EitherWorldExt::partition_either), splitting the entities into
+//! the ones given [`Either<T, U>`](Either)'s left and right items,
+//! [`either_of::<Q>(entity)`](EitherWorldExt::either_of), giving the kind of variant `entity` is
+//! given, and [`group_by_variant::<Q>()`](EitherWorldExt::group_by_variant), giving the entities
+//! of every variant.
+//!
+//! ## Table slices
+//!
+//! For bulk work over whole columns, a [`TableSliceState<Q>`](TableSliceState) gives a
+//! [`TableSliceQuery`] a table at a time, like `(&[Entity], Either<&[A], &[B]>)` for
+//! `Either<&A, &B>`, or `Either<&mut [A], &mut [B]>` for `Either<&mut A, &mut B>`. Only components
+//! [stored in tables](StorageType::Table) can be sliced, and mutable slices mark every component
+//! in them as changed.
+//!
+//! ## Inspecting archetypes
+//!
+//! The states of [`Either`], [`EitherBoth`], [`Either3`] to [`Either8`] and
+//! [`either_many!`](either_many) types, like [`EitherBothState`], have an `archetype_branches`
+//! method giving an [`ArchetypeBranch`] for every archetype in the world, with the variant its
+//! entities are given, if any, and the archetype's components that variant's branch requires or
+//! excludes. The state of `Q` can be made with `<Q as WorldQuery>::State::init(world)`.
+//!
+//! ## Diagnostics
+//!
+//! [`BevyEitherDiagnosticsPlugin`] records how many entities each variant of the registered
+//! queries is given every frame, along with how many archetypes they match, into bevy's
+//! [`Diagnostics`](bevy::diagnostic::Diagnostics), like
+//! `BevyEitherDiagnosticsPlugin::default().with::<MyEither<'static>>("my_either")`. Any
+//! [`VariantQuery`] can be registered, which includes [`Either`], [`EitherBoth`], [`Either3`] to
+//! [`Either8`] and [`either_many!`](either_many) types.
+//!
+//! ## Run criteria
+//!
+//! [`run_if_any_variant::<Q>(kind)`](run_if_any_variant) only runs a system when at least one
+//! entity is given the variant `kind` of `Q`, like `run_if_any_variant::<MyEither<'static>>(
+//! MyEitherKind::MyVariant)`, and [`run_if_any_left`] and [`run_if_any_right`] do the same for
+//! [`Either`]. This is decided from the matched archetypes, without looking at the entities.
+//!
+//! ## Entered and exited events
+//!
+//! Adding the [`EitherEventsPlugin<Q>`](EitherEventsPlugin) sends an
+//! [`EitherEntered<Q>`](EitherEntered) event whenever an entity starts matching `Q`, and an
+//! [`EitherExited<Q>`](EitherExited) event whenever it stops, along with the variant it entered or
+//! left. An entity changing variants keeps matching `Q`, so it sends neither.
+//!
+//! ## System parameters
+//!
+//! [`Either<P1, P2>`](Either) and [`EitherBoth<P1, P2>`](EitherBoth) are also
+//! [system parameters](bevy::ecs::system::SystemParam) when both `P1` and `P2` are
+//! [optional parameters](OptionalParam), like [`Res`] and [`ResMut`]. They resolve to whichever
+//! parameter exists when the system runs, with the same priority as the queries, and panic if
+//! neither exists. Access to both parameters is always registered.
+//!
+//! ## Bundles
+//!
+//! [`Either`] and [`EitherBoth`] can't be [bundles](bevy::ecs::bundle::Bundle). In Bevy 0.5,
+//! [`Bundle::type_info`] takes only the type, and inserting a bundle moves the entity to the
+//! archetype those components lead to before the value is looked at, so the components can't
+//! depend on the variant. Implementing it anyway would write the missing side's components from
+//! uninitialized memory. The [`InsertEither`] trait instead lets entities insert the bundle (or
+//! both bundles) contained in either of them.
+//!
+//! ## The `unchecked` feature
+//!
+//! A fetcher is only given archetypes and tables that one of its branches matches, so it panics if
+//! none of them do. Outside of debug builds, the `unchecked` feature makes this undefined behavior
+//! instead, letting [`Either`], [`EitherBoth`], [`Either3`] to [`Either8`] and
+//! [`either_many!`](either_many) types skip matching their last branch when none of the others
+//! match.
+//!
 //! [world query]: WorldQuery
 //! [world queries]: WorldQuery
 
 #![no_std]
 
+extern crate alloc;
+
 use bevy::prelude::*;
 use bevy::ecs::{storage::*, component::*, archetype::*, query::*};
+use unchecked::{unmatched, UNCHECKED};
 
+mod access;
+mod at_least;
+mod bundle;
+mod by_variant;
+mod config;
+mod diagnostics;
+mod dynamic;
 mod either_both;
 mod either;
 mod either_many;
+mod either_n;
+mod events;
+mod get;
+mod inline;
+mod not;
+mod param;
+mod run_criteria;
+mod shadow;
+mod slices;
+mod unchecked;
+mod variant;
+mod world;
 
-pub use either_both::EitherBoth;
-pub use either::Either;
+pub use at_least::AtLeast;
+pub use bundle::{EnumComponents, InsertEither};
+pub use bevy_either_derive::EnumComponents;
+pub use by_variant::{VariantIter, VariantQueryState};
+pub use config::{Configured, ConfiguredQuery, VariantConfig, VariantFetch, WithEnabledVariant};
+pub use diagnostics::BevyEitherDiagnosticsPlugin;
+pub use dynamic::{DynamicBranch, DynamicEither, DynamicEitherBranches, DynamicEitherItem};
+pub use either_both::{EitherBoth, EitherBothKind, EitherBothState};
+pub use either::{Either, EitherKind, EitherState};
+pub use either_n::{
+    Either3, Either3Kind, Either4, Either4Kind, Either5, Either5Kind, Either6, Either6Kind, Either7,
+    Either7Kind, Either8, Either8Kind, Flatten3, Flatten4, Flatten5, Flatten6, Flatten7, Flatten8,
+    FlattenBoth3, FlattenBoth4, FlattenBoth5, FlattenBoth6, FlattenBoth7, FlattenBoth8, LeftNested,
+    RightNested,
+};
+pub use events::{EitherEntered, EitherEventsPlugin, EitherExited};
+pub use get::{EitherQueryError, EitherQueryExt};
+pub use not::NotQuery;
+pub use param::{OptionalParam, OptionalParamFetch};
+pub use run_criteria::{run_if_any_left, run_if_any_right, run_if_any_variant};
+pub use shadow::{
+    Clause, QueryRequirements, Requirements, Variants, assert_no_shadowed_variants,
+    shadowed_variants, warn_shadowed_variants,
+};
+pub use slices::{ReadOnlyTableSlice, TableSliceFetch, TableSliceQuery, TableSliceState};
+pub use variant::{ArchetypeBranch, VariantArchetypes, VariantQuery};
+pub use world::EitherWorldExt;
 
 pub mod exports {
     pub use paste::paste;
+    pub use alloc::vec::Vec;
+    pub use super::access::{add_exclusive_branch_access, branch_components};
+    pub use super::get::ReadOnlyGet;
+    pub use super::unchecked::{unmatched, UNCHECKED};
 }
@@ -0,0 +1,128 @@
+use super::{*, shadow::*};
+use core::marker::PhantomData;
+
+/// A [filter](bevy::ecs::query::FilterFetch) matching exactly the archetypes that `Q` doesn't,
+/// like [`Without<T>`] for any [world query](WorldQuery).
+///
+/// Nothing is fetched from `Q`, so the item is always `true`, and `NotQuery<Q>` can be used in
+/// either the query or the filter of a [`Query`].
+pub struct NotQuery<Q>(PhantomData<fn() -> Q>);
+
+pub struct NotQueryState<S> {
+    state: S,
+}
+
+unsafe impl<S: FetchState> FetchState for NotQueryState<S> {
+    fn init(world: &mut World) -> Self {
+        NotQueryState {
+            state: S::init(world),
+        }
+    }
+
+    // nothing is fetched, so there's no access
+    fn update_component_access(&self, _access: &mut FilteredAccess<ComponentId>) {}
+
+    fn update_archetype_component_access(
+        &self,
+        _archetype: &Archetype,
+        _access: &mut Access<ArchetypeComponentId>,
+    ) {}
+
+    fn matches_archetype(&self, archetype: &Archetype) -> bool {
+        !self.state.matches_archetype(archetype)
+    }
+
+    fn matches_table(&self, table: &Table) -> bool {
+        !self.state.matches_table(table)
+    }
+}
+
+pub struct NotQueryFetch<F> {
+    dense: bool,
+    marker: PhantomData<fn() -> F>,
+}
+
+impl<'w, F: Fetch<'w>> Fetch<'w> for NotQueryFetch<F> {
+    type Item = bool;
+    type State = NotQueryState<F::State>;
+
+    fn is_dense(&self) -> bool {
+        // a table can't tell the archetypes without `Q`'s sparse components apart
+        self.dense
+    }
+
+    unsafe fn init(
+        world: &World,
+        state: &Self::State,
+        last_change_tick: u32,
+        change_tick: u32,
+    ) -> Self {
+        NotQueryFetch {
+            dense: F::init(world, &state.state, last_change_tick, change_tick).is_dense(),
+            marker: PhantomData,
+        }
+    }
+
+    unsafe fn set_archetype(
+        &mut self,
+        _state: &Self::State,
+        _archetype: &Archetype,
+        _tables: &Tables,
+    ) {}
+
+    unsafe fn set_table(&mut self, _state: &Self::State, _table: &Table) {}
+
+    unsafe fn archetype_fetch(&mut self, _archetype_index: usize) -> bool {
+        true
+    }
+
+    unsafe fn table_fetch(&mut self, _table_row: usize) -> bool {
+        true
+    }
+}
+
+unsafe impl<F> ReadOnlyFetch for NotQueryFetch<F> {}
+
+impl<Q: WorldQuery> WorldQuery for NotQuery<Q> {
+    type Fetch = NotQueryFetch<Q::Fetch>;
+    type State = NotQueryState<Q::State>;
+}
+
+impl<Q: QueryRequirements> QueryRequirements for NotQuery<Q> {
+    fn requirements() -> Requirements {
+        Q::requirements().negate()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    #[test]
+    fn test_not_query() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet)).unwrap();
+        let none = world.spawn().id();
+        world.spawn().insert(A);
+        let b = world.spawn().insert(B).id();
+        world.spawn().insert(A).insert(B);
+
+        let mut query = world.query_filtered::<Entity, NotQuery<Either<&A, (&B, Without<B>)>>>();
+        let mut entities = query.iter(&world).collect::<alloc::vec::Vec<_>>();
+        entities.sort();
+        assert_eq!(entities, [none, b]);
+
+        let mut query = world.query_filtered::<Entity, NotQuery<(&A, &B)>>();
+        assert_eq!(query.iter(&world).count(), 3);
+
+        assert!(NotQuery::<(&A, &B)>::requirements() == Requirements::without::<A>()
+            .or(&Requirements::without::<B>()));
+        assert!(Requirements::with::<A>().implies(&NotQuery::<Without<A>>::requirements()));
+        assert!(shadowed_variants::<Either<NotQuery<&A>, Without<A>>>() == ["Right"]);
+    }
+}
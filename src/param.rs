@@ -0,0 +1,241 @@
+use super::*;
+use bevy::ecs::system::{
+    OptionResMutState, OptionResState, SystemParam, SystemParamFetch, SystemParamState,
+    SystemState,
+};
+
+/// A [system parameter](SystemParam) that may be missing, like a resource. These are the
+/// parameters that can be used as either side of [`Either`] and [`EitherBoth`] system parameters.
+pub trait OptionalParam: SystemParam {
+    /// The [state](SystemParamState) of the parameter that fetches it only if it exists.
+    type OptionFetch: for<'a> OptionalParamFetch<'a>;
+}
+
+/// The [fetcher](SystemParamFetch) of an [optional parameter](OptionalParam).
+pub trait OptionalParamFetch<'a>: SystemParamFetch<'a> {
+    /// The item given when the parameter does exist.
+    type Inner;
+
+    /// Converts the fetched item into the inner item, or [`None`] if the parameter doesn't exist.
+    fn into_option(item: Self::Item) -> Option<Self::Inner>;
+}
+
+impl<'a, T: Component> OptionalParam for Res<'a, T> {
+    type OptionFetch = OptionResState<T>;
+}
+
+impl<'a, T: Component> OptionalParamFetch<'a> for OptionResState<T> {
+    type Inner = Res<'a, T>;
+
+    #[inline]
+    fn into_option(item: Self::Item) -> Option<Self::Inner> {
+        item
+    }
+}
+
+impl<'a, T: Component> OptionalParam for ResMut<'a, T> {
+    type OptionFetch = OptionResMutState<T>;
+}
+
+impl<'a, T: Component> OptionalParamFetch<'a> for OptionResMutState<T> {
+    type Inner = ResMut<'a, T>;
+
+    #[inline]
+    fn into_option(item: Self::Item) -> Option<Self::Inner> {
+        item
+    }
+}
+
+pub struct EitherParamState<T, U> {
+    left_state: T,
+    right_state: U,
+}
+
+// SAFE: the access of both sides is registered, so the access covers whichever side is fetched.
+unsafe impl<T: SystemParamState, U: SystemParamState> SystemParamState
+for EitherParamState<T, U> {
+    type Config = (T::Config, U::Config);
+
+    fn init(world: &mut World, system_state: &mut SystemState, config: Self::Config) -> Self {
+        EitherParamState {
+            left_state: T::init(world, system_state, config.0),
+            right_state: U::init(world, system_state, config.1),
+        }
+    }
+
+    fn new_archetype(&mut self, archetype: &Archetype, system_state: &mut SystemState) {
+        self.left_state.new_archetype(archetype, system_state);
+        self.right_state.new_archetype(archetype, system_state);
+    }
+
+    fn apply(&mut self, world: &mut World) {
+        self.left_state.apply(world);
+        self.right_state.apply(world);
+    }
+
+    fn default_config() -> Self::Config {
+        (T::default_config(), U::default_config())
+    }
+}
+
+impl<T: OptionalParam, U: OptionalParam> SystemParam for Either<T, U> {
+    type Fetch = EitherParamState<T::OptionFetch, U::OptionFetch>;
+}
+
+impl<'a, T: OptionalParamFetch<'a>, U: OptionalParamFetch<'a>> SystemParamFetch<'a>
+for EitherParamState<T, U> {
+    type Item = Either<T::Inner, U::Inner>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'a mut Self,
+        system_state: &'a SystemState,
+        world: &'a World,
+        change_tick: u32,
+    ) -> Self::Item {
+        let EitherParamState { left_state, right_state } = state;
+        let left = T::get_param(left_state, system_state, world, change_tick);
+        if let Some(left) = T::into_option(left) {
+            return Either::Left(left);
+        }
+        let right = U::get_param(right_state, system_state, world, change_tick);
+        match U::into_option(right) {
+            Some(right) => Either::Right(right),
+            None => panic!(
+                "Neither requested parameter exists: {} or {}",
+                core::any::type_name::<T::Inner>(),
+                core::any::type_name::<U::Inner>(),
+            ),
+        }
+    }
+}
+
+impl<T: OptionalParam, U: OptionalParam> SystemParam for EitherBoth<T, U> {
+    type Fetch = EitherBothParamState<T::OptionFetch, U::OptionFetch>;
+}
+
+pub struct EitherBothParamState<T, U>(EitherParamState<T, U>);
+
+// SAFE: same access as `EitherParamState`.
+unsafe impl<T: SystemParamState, U: SystemParamState> SystemParamState
+for EitherBothParamState<T, U> {
+    type Config = (T::Config, U::Config);
+
+    fn init(world: &mut World, system_state: &mut SystemState, config: Self::Config) -> Self {
+        EitherBothParamState(EitherParamState::init(world, system_state, config))
+    }
+
+    fn new_archetype(&mut self, archetype: &Archetype, system_state: &mut SystemState) {
+        self.0.new_archetype(archetype, system_state);
+    }
+
+    fn apply(&mut self, world: &mut World) {
+        self.0.apply(world);
+    }
+
+    fn default_config() -> Self::Config {
+        EitherParamState::<T, U>::default_config()
+    }
+}
+
+impl<'a, T: OptionalParamFetch<'a>, U: OptionalParamFetch<'a>> SystemParamFetch<'a>
+for EitherBothParamState<T, U> {
+    type Item = EitherBoth<T::Inner, U::Inner>;
+
+    #[inline]
+    unsafe fn get_param(
+        state: &'a mut Self,
+        system_state: &'a SystemState,
+        world: &'a World,
+        change_tick: u32,
+    ) -> Self::Item {
+        let EitherParamState { left_state, right_state } = &mut state.0;
+        let left = T::into_option(T::get_param(left_state, system_state, world, change_tick));
+        let right = U::into_option(U::get_param(right_state, system_state, world, change_tick));
+        match (left, right) {
+            (Some(left), Some(right)) => EitherBoth::Both(left, right),
+            (Some(left), None) => EitherBoth::Left(left),
+            (None, Some(right)) => EitherBoth::Right(right),
+            (None, None) => panic!(
+                "Neither requested parameter exists: {} or {}",
+                core::any::type_name::<T::Inner>(),
+                core::any::type_name::<U::Inner>(),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct GpuSettings(u32);
+
+    #[derive(Debug, PartialEq, Eq)]
+    struct CpuSettings(u32);
+
+    #[derive(Debug, PartialEq, Eq, Default)]
+    struct Seen(Option<EitherBoth<u32, u32>>);
+
+    fn run_either(world: &mut World) -> Option<EitherBoth<u32, u32>> {
+        world.insert_resource(Seen::default());
+        let mut update_stage = SystemStage::single((|
+                settings: Either<Res<GpuSettings>, ResMut<CpuSettings>>,
+                mut seen: ResMut<Seen>,
+            | {
+                seen.0 = Some(match settings {
+                    Either::Left(gpu) => EitherBoth::Left(gpu.0),
+                    Either::Right(cpu) => EitherBoth::Right(cpu.0),
+                });
+            }
+        ).system());
+        update_stage.run(world);
+        world.get_resource::<Seen>().unwrap().0
+    }
+
+    fn run_either_both(world: &mut World) -> Option<EitherBoth<u32, u32>> {
+        world.insert_resource(Seen::default());
+        let mut update_stage = SystemStage::single((|
+                settings: EitherBoth<Res<GpuSettings>, Res<CpuSettings>>,
+                mut seen: ResMut<Seen>,
+            | {
+                seen.0 = Some(match settings {
+                    EitherBoth::Left(gpu) => EitherBoth::Left(gpu.0),
+                    EitherBoth::Right(cpu) => EitherBoth::Right(cpu.0),
+                    EitherBoth::Both(gpu, cpu) => EitherBoth::Both(gpu.0, cpu.0),
+                });
+            }
+        ).system());
+        update_stage.run(world);
+        world.get_resource::<Seen>().unwrap().0
+    }
+
+    #[test]
+    fn test_either_param() {
+        let mut world = World::default();
+        world.insert_resource(CpuSettings(2));
+        assert_eq!(run_either(&mut world), Some(EitherBoth::Right(2)));
+        world.insert_resource(GpuSettings(1));
+        assert_eq!(run_either(&mut world), Some(EitherBoth::Left(1)));
+    }
+
+    #[test]
+    fn test_eitherboth_param() {
+        let mut world = World::default();
+        world.insert_resource(GpuSettings(1));
+        assert_eq!(run_either_both(&mut world), Some(EitherBoth::Left(1)));
+        world.insert_resource(CpuSettings(2));
+        assert_eq!(run_either_both(&mut world), Some(EitherBoth::Both(1, 2)));
+        world.remove_resource::<GpuSettings>();
+        assert_eq!(run_either_both(&mut world), Some(EitherBoth::Right(2)));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_either_param_missing() {
+        let mut world = World::default();
+        run_either(&mut world);
+    }
+}
@@ -0,0 +1,79 @@
+use super::*;
+use bevy::ecs::{schedule::ShouldRun, system::System};
+
+/// A [run criteria](bevy::ecs::schedule::RunCriteria) running the system only when at least one
+/// entity is given the variant `kind` of `Q`, like
+/// `run_if_any_variant::<MyEither<'static>>(MyEitherKind::Attacking)`.
+///
+/// Only the archetypes are looked at, so this doesn't depend on the number of entities.
+pub fn run_if_any_variant<Q: VariantQuery + 'static>(
+    kind: Q::Kind,
+) -> impl System<In = (), Out = ShouldRun> {
+    (move |mut variants: Local<VariantArchetypes<Q>>, archetypes: &Archetypes| {
+        variants.update(archetypes);
+        if variants.any(archetypes, kind) {
+            ShouldRun::Yes
+        } else {
+            ShouldRun::No
+        }
+    }).system()
+}
+
+/// A [run criteria](bevy::ecs::schedule::RunCriteria) running the system only when at least one
+/// entity is given [`Either<T, U>`](Either)'s [left](Either::Left) item.
+pub fn run_if_any_left<T, U>() -> impl System<In = (), Out = ShouldRun>
+where
+    T: WorldQuery + 'static,
+    U: WorldQuery + 'static,
+{
+    run_if_any_variant::<Either<T, U>>(EitherKind::Left)
+}
+
+/// A [run criteria](bevy::ecs::schedule::RunCriteria) running the system only when at least one
+/// entity is given [`Either<T, U>`](Either)'s [right](Either::Right) item.
+pub fn run_if_any_right<T, U>() -> impl System<In = (), Out = ShouldRun>
+where
+    T: WorldQuery + 'static,
+    U: WorldQuery + 'static,
+{
+    run_if_any_variant::<Either<T, U>>(EitherKind::Right)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    #[derive(Default)]
+    struct Runs(u32, u32);
+
+    #[test]
+    fn test_run_criteria() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet)).unwrap();
+        world.insert_resource(Runs::default());
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system((|mut runs: ResMut<Runs>| runs.0 += 1)
+                .system()
+                .with_run_criteria(run_if_any_left::<&A, &B>()))
+            .add_system((|mut runs: ResMut<Runs>| runs.1 += 1)
+                .system()
+                .with_run_criteria(run_if_any_right::<&A, &B>()));
+
+        stage.run(&mut world);
+        let a = world.spawn().insert(A).insert(B).id();
+        stage.run(&mut world);
+        world.entity_mut(a).remove::<A>();
+        stage.run(&mut world);
+        world.despawn(a);
+        stage.run(&mut world);
+
+        let runs = world.get_resource::<Runs>().unwrap();
+        assert_eq!((runs.0, runs.1), (1, 1));
+    }
+}
@@ -0,0 +1,323 @@
+use super::*;
+use core::any::{type_name, TypeId};
+use alloc::vec::Vec;
+
+/// The most components [`Requirements::implies`] enumerates. Past this, it gives up and assumes
+/// there is no implication.
+const MAX_FREE_COMPONENTS: usize = 16;
+
+/// A set of components that must all be present, and a set of components that must all be absent.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Clause {
+    pub with: Vec<TypeId>,
+    pub without: Vec<TypeId>,
+}
+
+impl Clause {
+    /// Returns whether any archetype can match this clause.
+    pub fn is_satisfiable(&self) -> bool {
+        !self.with.iter().any(|id| self.without.contains(id))
+    }
+
+    fn matches(&self, present: &[TypeId]) -> bool {
+        self.with.iter().all(|id| present.contains(id))
+            && !self.without.iter().any(|id| present.contains(id))
+    }
+
+    fn and(&self, other: &Clause) -> Clause {
+        let mut clause = self.clone();
+        for &id in other.with.iter() {
+            if !clause.with.contains(&id) {
+                clause.with.push(id);
+            }
+        }
+        for &id in other.without.iter() {
+            if !clause.without.contains(&id) {
+                clause.without.push(id);
+            }
+        }
+        clause
+    }
+}
+
+/// The archetypes a [world query](WorldQuery) matches, as any of many [clauses](Clause).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Requirements {
+    pub clauses: Vec<Clause>,
+}
+
+impl Requirements {
+    /// Matches every archetype.
+    pub fn always() -> Self {
+        Requirements { clauses: alloc::vec![Clause::default()] }
+    }
+
+    /// Matches no archetype.
+    pub fn never() -> Self {
+        Requirements { clauses: Vec::new() }
+    }
+
+    /// Matches the archetypes containing `T`.
+    pub fn with<T: 'static>() -> Self {
+        Requirements {
+            clauses: alloc::vec![Clause { with: alloc::vec![TypeId::of::<T>()], without: Vec::new() }],
+        }
+    }
+
+    /// Matches the archetypes not containing `T`.
+    pub fn without<T: 'static>() -> Self {
+        Requirements {
+            clauses: alloc::vec![Clause { with: Vec::new(), without: alloc::vec![TypeId::of::<T>()] }],
+        }
+    }
+
+    /// Matches the archetypes matched by both `self` and `other`.
+    pub fn and(&self, other: &Requirements) -> Self {
+        let clauses = self.clauses
+            .iter()
+            .flat_map(|l| other.clauses.iter().map(move |r| l.and(r)))
+            .filter(Clause::is_satisfiable)
+            .collect();
+        Requirements { clauses }
+    }
+
+    /// Matches the archetypes matched by either `self` or `other`.
+    pub fn or(&self, other: &Requirements) -> Self {
+        let mut clauses = self.clauses.clone();
+        clauses.extend(other.clauses.iter().cloned());
+        Requirements { clauses }
+    }
+
+    /// Matches the archetypes not matched by `self`.
+    pub fn negate(&self) -> Self {
+        self.clauses.iter().fold(Requirements::always(), |requirements, clause| {
+            // an archetype fails the clause by missing any of `with` or having any of `without`
+            let failing = Requirements {
+                clauses: clause.with
+                    .iter()
+                    .map(|&id| Clause { with: Vec::new(), without: alloc::vec![id] })
+                    .chain(clause.without
+                        .iter()
+                        .map(|&id| Clause { with: alloc::vec![id], without: Vec::new() }))
+                    .collect(),
+            };
+            requirements.and(&failing)
+        })
+    }
+
+    /// Returns whether any archetype can be matched.
+    pub fn is_satisfiable(&self) -> bool {
+        self.clauses.iter().any(Clause::is_satisfiable)
+    }
+
+    /// Returns whether every archetype matched by `self` is also matched by `other`.
+    ///
+    /// This may give false negatives when `other` mentions very many components.
+    pub fn implies(&self, other: &Requirements) -> bool {
+        self.clauses.iter().filter(|c| c.is_satisfiable()).all(|clause| {
+            let mut free = Vec::new();
+            for c in other.clauses.iter() {
+                for &id in c.with.iter().chain(c.without.iter()) {
+                    if !clause.with.contains(&id)
+                        && !clause.without.contains(&id)
+                        && !free.contains(&id)
+                    {
+                        free.push(id);
+                    }
+                }
+            }
+            if free.len() > MAX_FREE_COMPONENTS {
+                return false;
+            }
+            let mut present = Vec::new();
+            (0..1u32 << free.len()).all(|mask| {
+                present.clear();
+                present.extend(clause.with.iter().copied());
+                present.extend(free
+                    .iter()
+                    .enumerate()
+                    .filter(|&(i, _)| mask & (1 << i) != 0)
+                    .map(|(_, &id)| id));
+                other.clauses.iter().any(|c| c.matches(&present))
+            })
+        })
+    }
+}
+
+/// A [world query](WorldQuery) whose matched archetypes are known statically.
+pub trait QueryRequirements {
+    /// The archetypes this query matches.
+    fn requirements() -> Requirements;
+}
+
+/// A [world query](WorldQuery) that gives one of many variants, in priority order.
+pub trait Variants {
+    /// The name and [requirements](Requirements) of every variant, from highest to lowest
+    /// priority.
+    fn variants() -> Vec<(&'static str, Requirements)>;
+}
+
+/// Returns the names of the variants of `Q` that can never be given, either because a higher
+/// priority variant always matches first or because they can't match anything.
+pub fn shadowed_variants<Q: Variants>() -> Vec<&'static str> {
+    let mut higher = Requirements::never();
+    let mut shadowed = Vec::new();
+    for (name, requirements) in Q::variants() {
+        if requirements.implies(&higher) {
+            shadowed.push(name);
+        }
+        higher = higher.or(&requirements);
+    }
+    shadowed
+}
+
+/// [Warns](bevy::log::warn) about each [shadowed variant](shadowed_variants) of `Q`, returning
+/// whether there were any.
+pub fn warn_shadowed_variants<Q: Variants>() -> bool {
+    let shadowed = shadowed_variants::<Q>();
+    for name in shadowed.iter() {
+        bevy::log::warn!("variant {} of {} can never be matched", name, type_name::<Q>());
+    }
+    !shadowed.is_empty()
+}
+
+/// Panics if `Q` has any [shadowed variant](shadowed_variants).
+pub fn assert_no_shadowed_variants<Q: Variants>() {
+    let shadowed = shadowed_variants::<Q>();
+    if !shadowed.is_empty() {
+        panic!("variants {:?} of {} can never be matched", shadowed, type_name::<Q>());
+    }
+}
+
+impl<T: Component> QueryRequirements for &T {
+    fn requirements() -> Requirements {
+        Requirements::with::<T>()
+    }
+}
+
+impl<T: Component> QueryRequirements for &mut T {
+    fn requirements() -> Requirements {
+        Requirements::with::<T>()
+    }
+}
+
+impl<T: Component> QueryRequirements for With<T> {
+    fn requirements() -> Requirements {
+        Requirements::with::<T>()
+    }
+}
+
+impl<T: Component> QueryRequirements for Without<T> {
+    fn requirements() -> Requirements {
+        Requirements::without::<T>()
+    }
+}
+
+impl<T: Component> QueryRequirements for Added<T> {
+    fn requirements() -> Requirements {
+        Requirements::with::<T>()
+    }
+}
+
+impl<T: Component> QueryRequirements for Changed<T> {
+    fn requirements() -> Requirements {
+        Requirements::with::<T>()
+    }
+}
+
+impl<T: QueryRequirements> QueryRequirements for Option<T> {
+    fn requirements() -> Requirements {
+        Requirements::always()
+    }
+}
+
+impl QueryRequirements for bevy::ecs::entity::Entity {
+    fn requirements() -> Requirements {
+        Requirements::always()
+    }
+}
+
+impl<T: QueryRequirements, U: QueryRequirements> QueryRequirements for Either<T, U> {
+    fn requirements() -> Requirements {
+        T::requirements().or(&U::requirements())
+    }
+}
+
+impl<T: QueryRequirements, U: QueryRequirements> QueryRequirements for EitherBoth<T, U> {
+    fn requirements() -> Requirements {
+        T::requirements().or(&U::requirements())
+    }
+}
+
+impl<T: QueryRequirements, U: QueryRequirements> Variants for Either<T, U> {
+    fn variants() -> Vec<(&'static str, Requirements)> {
+        alloc::vec![("Left", T::requirements()), ("Right", U::requirements())]
+    }
+}
+
+macro_rules! impl_tuple_requirements {
+    ($($name:ident),*) => {
+        impl<$($name: QueryRequirements),*> QueryRequirements for ($($name,)*) {
+            fn requirements() -> Requirements {
+                Requirements::always()
+                    $(.and(&$name::requirements()))*
+            }
+        }
+
+        impl<$($name: QueryRequirements),*> QueryRequirements for Or<($($name,)*)> {
+            fn requirements() -> Requirements {
+                Requirements::never()
+                    $(.or(&$name::requirements()))*
+            }
+        }
+    };
+}
+
+impl_tuple_requirements!();
+impl_tuple_requirements!(A);
+impl_tuple_requirements!(A, B);
+impl_tuple_requirements!(A, B, C);
+impl_tuple_requirements!(A, B, C, D);
+impl_tuple_requirements!(A, B, C, D, E);
+impl_tuple_requirements!(A, B, C, D, E, F);
+impl_tuple_requirements!(A, B, C, D, E, F, G);
+impl_tuple_requirements!(A, B, C, D, E, F, G, H);
+impl_tuple_requirements!(A, B, C, D, E, F, G, H, I);
+impl_tuple_requirements!(A, B, C, D, E, F, G, H, I, J);
+impl_tuple_requirements!(A, B, C, D, E, F, G, H, I, J, K);
+impl_tuple_requirements!(A, B, C, D, E, F, G, H, I, J, K, L);
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    struct C;
+
+    #[test]
+    fn test_either_shadowing() {
+        assert!(shadowed_variants::<Either<&A, &B>>().is_empty());
+        assert!(shadowed_variants::<Either<&A, (&A, &B)>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<&A, (&mut A, With<B>)>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<(&A, &B), &A>>().is_empty());
+        assert!(shadowed_variants::<Either<Option<&A>, &B>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<Entity, (&A, &B)>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<(&A, Without<A>), &B>>() == ["Left"]);
+        assert!(shadowed_variants::<Either<Without<A>, &B>>().is_empty());
+        assert!(shadowed_variants::<Either<Either<&A, Without<A>>, &B>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<Or<(With<A>, With<B>)>, (&B, &C)>>() == ["Right"]);
+        assert!(shadowed_variants::<Either<EitherBoth<&A, &B>, (&C, Without<A>)>>().is_empty());
+        assert!(!warn_shadowed_variants::<Either<&A, &B>>());
+        assert!(warn_shadowed_variants::<Either<&A, &A>>());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_assert_shadowing() {
+        assert_no_shadowed_variants::<Either<With<A>, (&A, &C)>>();
+    }
+}
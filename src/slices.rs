@@ -0,0 +1,258 @@
+use super::*;
+use bevy::ecs::world::WorldId;
+use core::{marker::PhantomData, slice};
+
+/// A [world query](WorldQuery) whose items can be given a whole [table](Table) at a time, as
+/// slices of the table's columns.
+///
+/// This is implemented for `&T` and `&mut T` over components
+/// [stored in tables](StorageType::Table), and for [`Either`] over such queries, giving the slices
+/// of whichever side the table matches.
+pub trait TableSliceQuery {
+    type State: Send + Sync + 'static;
+
+    fn init_state(world: &mut World) -> Self::State;
+
+    fn matches_table(state: &Self::State, table: &Table) -> bool;
+}
+
+/// Gives the slices of a [`TableSliceQuery`] borrowing from the world for `'w`.
+///
+/// # Safety
+///
+/// `table_slice` must only access the components that the query reads or writes.
+pub unsafe trait TableSliceFetch<'w>: TableSliceQuery {
+    type Slice;
+
+    /// # Safety
+    ///
+    /// `table` must match, and the components of the slice must not be aliased mutably.
+    unsafe fn table_slice(state: &Self::State, table: &'w Table, change_tick: u32) -> Self::Slice;
+}
+
+/// Marks a [`TableSliceQuery`] as only reading from the world.
+///
+/// # Safety
+///
+/// The slices must not give mutable access to anything.
+pub unsafe trait ReadOnlyTableSlice: TableSliceQuery {}
+
+fn table_component_id<T: Component>(world: &mut World) -> ComponentId {
+    let info = world.components_mut().get_or_insert_info::<T>();
+    if info.storage_type() != StorageType::Table {
+        panic!("Table slices of {} need it to be stored in tables.", info.name());
+    }
+    info.id()
+}
+
+impl<T: Component> TableSliceQuery for &T {
+    type State = ComponentId;
+
+    fn init_state(world: &mut World) -> ComponentId {
+        table_component_id::<T>(world)
+    }
+
+    fn matches_table(&component_id: &ComponentId, table: &Table) -> bool {
+        table.has_column(component_id)
+    }
+}
+
+unsafe impl<'w, T: Component> TableSliceFetch<'w> for &T {
+    type Slice = &'w [T];
+
+    unsafe fn table_slice(&component_id: &ComponentId, table: &'w Table, _: u32) -> &'w [T] {
+        let column = table.get_column(component_id).unwrap();
+        slice::from_raw_parts(column.get_ptr().cast::<T>().as_ptr(), table.len())
+    }
+}
+
+unsafe impl<T: Component> ReadOnlyTableSlice for &T {}
+
+impl<T: Component> TableSliceQuery for &mut T {
+    type State = ComponentId;
+
+    fn init_state(world: &mut World) -> ComponentId {
+        table_component_id::<T>(world)
+    }
+
+    fn matches_table(&component_id: &ComponentId, table: &Table) -> bool {
+        table.has_column(component_id)
+    }
+}
+
+unsafe impl<'w, T: Component> TableSliceFetch<'w> for &mut T {
+    type Slice = &'w mut [T];
+
+    unsafe fn table_slice(
+        &component_id: &ComponentId,
+        table: &'w Table,
+        change_tick: u32,
+    ) -> &'w mut [T] {
+        let column = table.get_column(component_id).unwrap();
+        // there's no telling which components get written through the slice
+        for row in 0..table.len() {
+            column.get_ticks_unchecked_mut(row).set_changed(change_tick);
+        }
+        slice::from_raw_parts_mut(column.get_ptr().cast::<T>().as_ptr(), table.len())
+    }
+}
+
+impl<T: TableSliceQuery, U: TableSliceQuery> TableSliceQuery for Either<T, U> {
+    type State = (T::State, U::State);
+
+    fn init_state(world: &mut World) -> Self::State {
+        (T::init_state(world), U::init_state(world))
+    }
+
+    fn matches_table((left, right): &Self::State, table: &Table) -> bool {
+        T::matches_table(left, table) || U::matches_table(right, table)
+    }
+}
+
+unsafe impl<'w, T, U> TableSliceFetch<'w> for Either<T, U>
+where
+    T: TableSliceFetch<'w>,
+    U: TableSliceFetch<'w>,
+{
+    type Slice = Either<T::Slice, U::Slice>;
+
+    unsafe fn table_slice(
+        (left, right): &Self::State,
+        table: &'w Table,
+        change_tick: u32,
+    ) -> Self::Slice {
+        if T::matches_table(left, table) {
+            Either::Left(T::table_slice(left, table, change_tick))
+        } else {
+            Either::Right(U::table_slice(right, table, change_tick))
+        }
+    }
+}
+
+unsafe impl<T: ReadOnlyTableSlice, U: ReadOnlyTableSlice> ReadOnlyTableSlice for Either<T, U> {}
+
+/// Gives the items of a [`TableSliceQuery`] a [table](Table) at a time, as the slices of the
+/// table's columns along with the slice of its entities, like `(&[Entity], Either<&[A], &[B]>)`.
+///
+/// Every component of a mutable slice is marked as changed, as it isn't known which of them are
+/// written to.
+pub struct TableSliceState<Q: TableSliceQuery> {
+    world_id: WorldId,
+    state: Q::State,
+    marker: PhantomData<fn() -> Q>,
+}
+
+impl<Q: TableSliceQuery> TableSliceState<Q> {
+    pub fn new(world: &mut World) -> Self {
+        TableSliceState {
+            world_id: world.id(),
+            state: Q::init_state(world),
+            marker: PhantomData,
+        }
+    }
+
+    fn validate_world(&self, world: &World) {
+        if world.id() != self.world_id {
+            panic!(
+                "Attempted to use {} with a mismatched World. TableSliceStates can only be used \
+                with the World they were created from.",
+                core::any::type_name::<Self>(),
+            );
+        }
+    }
+
+    /// Iterates over the slices of every non-empty matched table.
+    pub fn iter<'w, 's>(
+        &'s self,
+        world: &'w World,
+    ) -> impl Iterator<Item = (&'w [Entity], <Q as TableSliceFetch<'w>>::Slice)> + 's
+    where
+        'w: 's,
+        Q: TableSliceFetch<'w> + ReadOnlyTableSlice,
+    {
+        // SAFE: query is read only
+        unsafe { self.iter_unchecked(world) }
+    }
+
+    /// Mutably iterates over the slices of every non-empty matched table.
+    pub fn iter_mut<'w, 's>(
+        &'s self,
+        world: &'w mut World,
+    ) -> impl Iterator<Item = (&'w [Entity], <Q as TableSliceFetch<'w>>::Slice)> + 's
+    where
+        'w: 's,
+        Q: TableSliceFetch<'w>,
+    {
+        // SAFE: query has unique world access, and each table is only given once
+        unsafe { self.iter_unchecked(world) }
+    }
+
+    /// # Safety
+    ///
+    /// This does not check for mutable query correctness. To be safe, make sure mutable queries
+    /// have unique access to the components they query.
+    pub unsafe fn iter_unchecked<'w, 's>(
+        &'s self,
+        world: &'w World,
+    ) -> impl Iterator<Item = (&'w [Entity], <Q as TableSliceFetch<'w>>::Slice)> + 's
+    where
+        'w: 's,
+        Q: TableSliceFetch<'w>,
+    {
+        self.validate_world(world);
+        let change_tick = world.read_change_tick();
+        world.storages()
+            .tables
+            .iter()
+            .filter(move |table| !table.is_empty() && Q::matches_table(&self.state, table))
+            .map(move |table| (table.entities(), Q::table_slice(&self.state, table, change_tick)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use alloc::vec::Vec;
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct A(f32);
+
+    #[derive(Debug, PartialEq)]
+    struct B(f32);
+
+    #[test]
+    fn test_table_slices() {
+        let mut world = World::default();
+        let a = world.spawn().insert(A(1.0)).id();
+        world.spawn().insert(A(2.0));
+        world.spawn().insert(A(3.0)).insert(B(4.0));
+        let b = world.spawn().insert(B(5.0)).id();
+
+        let state = TableSliceState::<Either<&A, &B>>::new(&mut world);
+        let mut slices: Vec<_> = state.iter(&world).collect();
+        slices.sort_by_key(|(entities, _)| (entities.len(), entities[0].id()));
+        assert_eq!(slices.len(), 3);
+        assert_eq!(slices[0].1, Either::Left(&[A(3.0)][..]));
+        assert_eq!(slices[1], (&[b][..], Either::Right(&[B(5.0)][..])));
+        assert_eq!(slices[2].0.len(), 2);
+        assert_eq!(slices[2].0[0], a);
+        assert_eq!(slices[2].1, Either::Left(&[A(1.0), A(2.0)][..]));
+
+        let state = TableSliceState::<Either<&mut B, &mut A>>::new(&mut world);
+        world.clear_trackers();
+        for (entities, slice) in state.iter_mut(&mut world) {
+            match slice {
+                Either::Left(b) => b.iter_mut().for_each(|b| b.0 *= 10.0),
+                Either::Right(a) => {
+                    assert_eq!(entities.len(), a.len());
+                    a.iter_mut().for_each(|a| a.0 *= -1.0);
+                },
+            }
+        }
+        assert_eq!(world.get::<A>(a), Some(&A(-1.0)));
+        assert_eq!(world.get::<B>(b), Some(&B(50.0)));
+        let mut changed = world.query_filtered::<Entity, Changed<A>>();
+        assert_eq!(changed.iter(&world).count(), 2);
+    }
+}
@@ -0,0 +1,21 @@
+use core::fmt;
+
+/// Whether the `unchecked` feature is enabled outside of debug builds, letting fetchers assume that
+/// one of their branches always matches the archetypes and tables they're given.
+pub const UNCHECKED: bool = cfg!(all(feature = "unchecked", not(debug_assertions)));
+
+/// Called by a fetcher when none of its branches match, which its state's matching should have
+/// ruled out.
+///
+/// # Safety
+///
+/// When [`UNCHECKED`], this must never be called.
+#[inline(always)]
+#[track_caller]
+pub unsafe fn unmatched(message: fmt::Arguments) -> ! {
+    if UNCHECKED {
+        core::hint::unreachable_unchecked()
+    } else {
+        unreachable!("{}", message)
+    }
+}
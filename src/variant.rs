@@ -0,0 +1,156 @@
+use super::*;
+use alloc::vec::Vec;
+use bevy::ecs::world::FromWorld;
+use core::{fmt::Debug, hash::Hash};
+
+/// A [world query](WorldQuery) made of named variants, where an archetype alone decides which
+/// variant its entities are given.
+///
+/// This is implemented for [`Either`], [`EitherBoth`], [`Either3`] to [`Either8`] and
+/// [`either_many!`](either_many) types.
+pub trait VariantQuery: WorldQuery {
+    /// The variants without their items, like [`EitherKind`].
+    type Kind: Copy + Eq + Hash + Debug + Send + Sync + 'static;
+
+    /// Every kind of variant, in declaration order.
+    fn kinds() -> &'static [Self::Kind];
+
+    /// The name of every variant, in the same order as [`kinds`](VariantQuery::kinds).
+    fn variant_names() -> &'static [&'static str];
+
+    /// Returns the index into [`kinds`](VariantQuery::kinds) of the variant given to the entities
+    /// of `archetype`, or [`None`] if the query doesn't match it.
+    fn archetype_variant(state: &Self::State, archetype: &Archetype) -> Option<usize>;
+}
+
+/// How the state of a [`VariantQuery`] resolves an [`Archetype`], as given by the
+/// `archetype_branches` methods of [`EitherState`], [`EitherBothState`] and the states of
+/// [`Either3`] to [`Either8`] and [`either_many!`](either_many) types.
+pub struct ArchetypeBranch<'a, K> {
+    pub archetype: &'a Archetype,
+    /// The variant given to the archetype's entities, or [`None`] if the query doesn't match it.
+    pub kind: Option<K>,
+    components: Vec<ComponentId>,
+}
+
+impl<'a, K> ArchetypeBranch<'a, K> {
+    /// Creates the branch given to `archetype`, where `branch_components` are the components the
+    /// deciding branch requires or excludes.
+    #[doc(hidden)]
+    pub fn new(archetype: &'a Archetype, kind: Option<K>, branch_components: &[ComponentId]) -> Self {
+        ArchetypeBranch {
+            archetype,
+            kind,
+            components: branch_components
+                .iter()
+                .copied()
+                .filter(|&id| archetype.contains(id))
+                .collect(),
+        }
+    }
+
+    pub fn id(&self) -> ArchetypeId {
+        self.archetype.id()
+    }
+
+    /// Returns the components of the archetype that the branch of the given variant requires or
+    /// excludes, or none if the query doesn't match the archetype.
+    pub fn archetype_components(&self) -> impl Iterator<Item = ComponentId> + '_ {
+        self.components.iter().copied()
+    }
+}
+
+impl<T: WorldQuery, U: WorldQuery> VariantQuery for Either<T, U> {
+    type Kind = EitherKind;
+
+    fn kinds() -> &'static [EitherKind] {
+        &[EitherKind::Left, EitherKind::Right]
+    }
+
+    fn variant_names() -> &'static [&'static str] {
+        &["Left", "Right"]
+    }
+
+    fn archetype_variant(state: &Self::State, archetype: &Archetype) -> Option<usize> {
+        state.archetype_kind(archetype).map(|kind| kind as usize)
+    }
+}
+
+impl<T: WorldQuery, U: WorldQuery> VariantQuery for EitherBoth<T, U> {
+    type Kind = EitherBothKind;
+
+    fn kinds() -> &'static [EitherBothKind] {
+        &[EitherBothKind::Left, EitherBothKind::Right, EitherBothKind::Both]
+    }
+
+    fn variant_names() -> &'static [&'static str] {
+        &["Left", "Right", "Both"]
+    }
+
+    fn archetype_variant(state: &Self::State, archetype: &Archetype) -> Option<usize> {
+        state.archetype_kind(archetype).map(|kind| kind as usize)
+    }
+}
+
+/// The archetypes for which a [`VariantQuery`] resolves to each of its variants, updated as new
+/// archetypes are created.
+pub struct VariantArchetypes<Q: VariantQuery> {
+    state: Q::State,
+    seen: usize,
+    variants: Vec<Vec<ArchetypeId>>,
+}
+
+impl<Q: VariantQuery> FromWorld for VariantArchetypes<Q> {
+    fn from_world(world: &mut World) -> Self {
+        VariantArchetypes {
+            state: Q::State::init(world),
+            seen: 0,
+            variants: Q::kinds().iter().map(|_| Vec::new()).collect(),
+        }
+    }
+}
+
+impl<Q: VariantQuery> VariantArchetypes<Q> {
+    pub(crate) fn state(&self) -> &Q::State {
+        &self.state
+    }
+
+    /// Returns the archetypes of every variant, in declaration order, as of the last update.
+    pub(crate) fn variants(&self) -> &[Vec<ArchetypeId>] {
+        &self.variants
+    }
+
+    /// Sorts the archetypes created since the last update into their variants.
+    pub fn update(&mut self, archetypes: &Archetypes) {
+        for archetype in archetypes.iter().skip(self.seen) {
+            if let Some(variant) = Q::archetype_variant(&self.state, archetype) {
+                self.variants[variant].push(archetype.id());
+            }
+        }
+        self.seen = archetypes.len();
+    }
+
+    /// Returns the archetypes resolving to the variant `kind`, as of the last update.
+    pub fn archetypes(&self, kind: Q::Kind) -> &[ArchetypeId] {
+        match Q::kinds().iter().position(|&other| other == kind) {
+            Some(variant) => &self.variants[variant],
+            None => &[],
+        }
+    }
+
+    /// Returns whether any entity is given the variant `kind`, as of the last update.
+    pub fn any(&self, archetypes: &Archetypes, kind: Q::Kind) -> bool {
+        self.archetypes(kind).iter().any(|&id| !archetypes[id].is_empty())
+    }
+
+    /// Returns the entities given the variant `kind`, as of the last update.
+    pub fn entities<'a>(
+        &'a self,
+        archetypes: &'a Archetypes,
+        kind: Q::Kind,
+    ) -> impl Iterator<Item = Entity> + 'a {
+        self.archetypes(kind)
+            .iter()
+            .flat_map(move |&id| archetypes[id].entities().iter().copied())
+    }
+}
@@ -0,0 +1,86 @@
+use super::*;
+use alloc::vec::Vec;
+use bevy::ecs::world::FromWorld;
+
+/// Sorts the entities of a [`World`] by the variants of a [`VariantQuery`], without building a
+/// [`QueryState`] and matching over its items.
+pub trait EitherWorldExt {
+    /// Returns the entities given [`Either<T, U>`](Either)'s [left](Either::Left) item and the ones
+    /// given its [right](Either::Right) item.
+    fn partition_either<T, U>(&mut self) -> (Vec<Entity>, Vec<Entity>)
+    where
+        T: WorldQuery + 'static,
+        U: WorldQuery + 'static;
+
+    /// Returns the variant of `Q` given to `entity`, or [`None`] if `Q` doesn't match it or it
+    /// doesn't exist.
+    fn either_of<Q: VariantQuery + 'static>(&mut self, entity: Entity) -> Option<Q::Kind>;
+
+    /// Returns the entities given each variant of `Q`, in declaration order.
+    fn group_by_variant<Q: VariantQuery + 'static>(&mut self) -> Vec<(Q::Kind, Vec<Entity>)>;
+}
+
+impl EitherWorldExt for World {
+    fn partition_either<T, U>(&mut self) -> (Vec<Entity>, Vec<Entity>)
+    where
+        T: WorldQuery + 'static,
+        U: WorldQuery + 'static,
+    {
+        let mut groups = self.group_by_variant::<Either<T, U>>().into_iter();
+        let (_, left) = groups.next().unwrap();
+        let (_, right) = groups.next().unwrap();
+        (left, right)
+    }
+
+    fn either_of<Q: VariantQuery + 'static>(&mut self, entity: Entity) -> Option<Q::Kind> {
+        let state = Q::State::init(self);
+        let location = self.entities().get(entity)?;
+        let variant = Q::archetype_variant(&state, &self.archetypes()[location.archetype_id])?;
+        Some(Q::kinds()[variant])
+    }
+
+    fn group_by_variant<Q: VariantQuery + 'static>(&mut self) -> Vec<(Q::Kind, Vec<Entity>)> {
+        let mut variants = VariantArchetypes::<Q>::from_world(self);
+        variants.update(self.archetypes());
+        Q::kinds()
+            .iter()
+            .map(|&kind| (kind, variants.entities(self.archetypes(), kind).collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::prelude::*;
+    use alloc::vec;
+    use super::*;
+
+    struct A;
+
+    struct B;
+
+    #[test]
+    fn test_world_ext() {
+        let mut world = World::default();
+        world.register_component(ComponentDescriptor::new::<B>(StorageType::SparseSet)).unwrap();
+        let a = world.spawn().insert(A).id();
+        let ab = world.spawn().insert(A).insert(B).id();
+        let b = world.spawn().insert(B).id();
+        let none = world.spawn().id();
+        let despawned = world.spawn().id();
+        world.despawn(despawned);
+
+        let (mut left, right) = world.partition_either::<&A, &B>();
+        left.sort();
+        assert_eq!((left, right), (vec![a, ab], vec![b]));
+        assert_eq!(world.either_of::<Either<&A, &B>>(ab), Some(EitherKind::Left));
+        assert_eq!(world.either_of::<EitherBoth<&A, &B>>(ab), Some(EitherBothKind::Both));
+        assert_eq!(world.either_of::<Either<&A, &B>>(none), None);
+        assert_eq!(world.either_of::<Either<&A, &B>>(despawned), None);
+        assert_eq!(world.group_by_variant::<EitherBoth<&A, &B>>(), vec![
+            (EitherBothKind::Left, vec![a]),
+            (EitherBothKind::Right, vec![b]),
+            (EitherBothKind::Both, vec![ab]),
+        ]);
+    }
+}
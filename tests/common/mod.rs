@@ -0,0 +1,241 @@
+//! A harness generating random worlds along with a model of what every entity holds, to compare
+//! the items of queries against.
+
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+use bevy::ecs::{component::{Component, ComponentDescriptor, StorageType}, query::*};
+use core::fmt::Debug;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LeftElem(pub u32);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RightElem(pub u32);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Extra(pub u32);
+
+/// The number of worlds generated by [`check_worlds`].
+pub const WORLDS: u64 = 64;
+
+/// The number of times [`check_worlds`] checks a world, mutating it in between.
+pub const ROUNDS: usize = 4;
+
+/// A xorshift64* generator, so that every world can be regenerated from its seed.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Returns a number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn chance(&mut self) -> bool {
+        self.next_u64() & 1 == 0
+    }
+}
+
+/// The components the model expects an entity to have.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Entry {
+    pub left: Option<LeftElem>,
+    pub right: Option<RightElem>,
+    pub extra: Option<Extra>,
+}
+
+/// A world with random components, each randomly stored in tables or sparse sets, along with the
+/// model of its entities.
+pub struct RandomWorld {
+    pub world: World,
+    pub entities: Vec<(Entity, Entry)>,
+    pub despawned: Vec<Entity>,
+    rng: Rng,
+}
+
+impl RandomWorld {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = Rng::new(seed);
+        let mut world = World::default();
+        register::<LeftElem>(&mut world, &mut rng);
+        register::<RightElem>(&mut world, &mut rng);
+        register::<Extra>(&mut world, &mut rng);
+        let mut random = RandomWorld {
+            world,
+            entities: Vec::new(),
+            despawned: Vec::new(),
+            rng,
+        };
+        for _ in 0..16 + random.rng.below(48) {
+            random.spawn();
+        }
+        random
+    }
+
+    fn spawn(&mut self) {
+        let rng = &mut self.rng;
+        let entry = Entry {
+            left: rng.chance().then(|| LeftElem(rng.next_u32())),
+            right: rng.chance().then(|| RightElem(rng.next_u32())),
+            extra: rng.chance().then(|| Extra(rng.next_u32())),
+        };
+        let mut entity = self.world.spawn();
+        if let Some(left) = entry.left {
+            entity.insert(left);
+        }
+        if let Some(right) = entry.right {
+            entity.insert(right);
+        }
+        if let Some(extra) = entry.extra {
+            entity.insert(extra);
+        }
+        self.entities.push((entity.id(), entry));
+    }
+
+    /// Randomly spawns and despawns entities, and inserts and removes their components.
+    pub fn mutate(&mut self) {
+        for _ in 0..self.rng.below(24) {
+            if self.entities.is_empty() || self.rng.below(4) == 0 {
+                self.spawn();
+                continue;
+            }
+            let index = self.rng.below(self.entities.len());
+            let (entity, entry) = &mut self.entities[index];
+            let mut entity_mut = self.world.entity_mut(*entity);
+            match self.rng.below(7) {
+                0 => {
+                    entity_mut.despawn();
+                    self.despawned.push(self.entities.swap_remove(index).0);
+                },
+                1 => {
+                    let left = LeftElem(self.rng.next_u32());
+                    entity_mut.insert(left);
+                    entry.left = Some(left);
+                },
+                2 => {
+                    let right = RightElem(self.rng.next_u32());
+                    entity_mut.insert(right);
+                    entry.right = Some(right);
+                },
+                3 => {
+                    let extra = Extra(self.rng.next_u32());
+                    entity_mut.insert(extra);
+                    entry.extra = Some(extra);
+                },
+                4 => {
+                    entity_mut.remove::<LeftElem>();
+                    entry.left = None;
+                },
+                5 => {
+                    entity_mut.remove::<RightElem>();
+                    entry.right = None;
+                },
+                _ => {
+                    entity_mut.remove::<Extra>();
+                    entry.extra = None;
+                },
+            }
+        }
+    }
+
+    /// Returns the number of entities with only a [`LeftElem`], only a [`RightElem`], and both.
+    pub fn counts(&self) -> (u32, u32, u32) {
+        let (mut left, mut right, mut both) = (0, 0, 0);
+        for (_, entry) in self.entities.iter() {
+            match (entry.left, entry.right) {
+                (Some(_), None) => left += 1,
+                (None, Some(_)) => right += 1,
+                (Some(_), Some(_)) => both += 1,
+                (None, None) => {},
+            }
+        }
+        (left, right, both)
+    }
+}
+
+fn register<T: Component>(world: &mut World, rng: &mut Rng) {
+    let storage_type = if rng.chance() {
+        StorageType::Table
+    } else {
+        StorageType::SparseSet
+    };
+    world.register_component(ComponentDescriptor::new::<T>(storage_type)).unwrap();
+}
+
+/// Generates [`WORLDS`] random worlds, each checked [`ROUNDS`] times with the same state made by
+/// `init`, and mutated in between.
+pub fn check_worlds<S>(
+    mut init: impl FnMut(&mut World) -> S,
+    mut check: impl FnMut(&RandomWorld, &mut S),
+) {
+    for seed in 0..WORLDS {
+        let mut random = RandomWorld::new(seed);
+        let mut state = init(&mut random.world);
+        for _ in 0..ROUNDS {
+            check(&random, &mut state);
+            random.mutate();
+        }
+    }
+}
+
+/// Asserts that `get`, `iter` and `for_each` give every entity the item that `expected` gives its
+/// entry, with `owned` copying the items out of the world.
+///
+/// Mutable queries are allowed, as every item is copied out before the next one is fetched.
+pub fn assert_model<'w, Q, T>(
+    random: &'w RandomWorld,
+    state: &mut QueryState<(Entity, Q)>,
+    expected: impl Fn(&Entry) -> Option<T>,
+    owned: impl Fn(<Q::Fetch as Fetch<'w>>::Item) -> T,
+) where
+    Q: WorldQuery,
+    T: PartialEq + Debug,
+{
+    let world = &random.world;
+    let mut model = Vec::new();
+    for (entity, entry) in random.entities.iter() {
+        // SAFE: nothing else accesses the world, and the item doesn't outlive this iteration
+        let item = unsafe { state.get_unchecked(world, *entity) };
+        let item = item.ok().map(|(_, item)| owned(item));
+        assert_eq!(item, expected(entry), "get of {:?} with {:?}", entity, entry);
+        if let Some(item) = expected(entry) {
+            model.push((*entity, item));
+        }
+    }
+    for &entity in random.despawned.iter() {
+        // SAFE: same as above
+        let item = unsafe { state.get_unchecked(world, entity) };
+        assert!(item.is_err(), "get of despawned {:?}", entity);
+    }
+    model.sort_by_key(|(entity, _)| entity.to_bits());
+
+    // SAFE: same as above
+    let mut items: Vec<_> = unsafe { state.iter_unchecked(world) }
+        .map(|(entity, item)| (entity, owned(item)))
+        .collect();
+    items.sort_by_key(|(entity, _)| entity.to_bits());
+    assert_eq!(items, model, "iter");
+
+    let mut items = Vec::new();
+    // SAFE: same as above
+    unsafe {
+        state.for_each_unchecked(world, |(entity, item)| items.push((entity, owned(item))));
+    }
+    items.sort_by_key(|(entity, _)| entity.to_bits());
+    assert_eq!(items, model, "for_each");
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+use bevy_either::{either, match_either};
+
+pub struct A(u32);
+
+pub struct B(u32);
+
+pub struct C(u32);
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Counts([u32; 3]);
+
+fn count(mut q: Query<either![&A, &B, &mut C]>, mut counts: ResMut<Counts>) {
+    for item in q.iter_mut() {
+        let (i, value) = match_either!(item,
+            a => (0, a.0),
+            b => (1, b.0),
+            c => (2, c.0),
+        );
+        counts.0[i] += value;
+    }
+}
+
+#[test]
+fn either_and_match_either_macros() {
+    let mut world = World::default();
+    world.insert_resource(Counts::default());
+    world.spawn().insert(A(1)).insert(B(10)).insert(C(100));
+    world.spawn().insert(B(2)).insert(C(20));
+    world.spawn().insert(C(3));
+    world.spawn().insert(A(4));
+    world.spawn();
+    let mut update_stage = SystemStage::single(count.system());
+    update_stage.run(&mut world);
+    assert_eq!(world.get_resource::<Counts>().unwrap(), &Counts([5, 2, 3]));
+}
+
+#[test]
+fn single() {
+    let mut world = World::default();
+    world.spawn().insert(A(1));
+    let mut query = world.query::<either![&A]>();
+    for item in query.iter(&world) {
+        assert_eq!(match_either!(item, a => a.0), 1);
+    }
+}
@@ -1,75 +1,7 @@
-use bevy::prelude::*;
-use bevy_either::EitherBoth;
-
-#[derive(Clone, Copy)]
-pub struct LeftElem;
-
-#[derive(Clone, Copy)]
-pub struct RightElem;
-
-fn push_entities(world: &mut World) -> (u32, u32, u32) {
-    // 0 = None, 1 = Some(Left), 2 = Some(Right), 3 = Some(Both)
-    static SUPERPERM: &[Option<EitherBoth<LeftElem, RightElem>>] = &[
-        None,
-        Some(EitherBoth::Left(LeftElem)),
-        Some(EitherBoth::Right(RightElem)),
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        None,
-        Some(EitherBoth::Left(LeftElem)),
-        Some(EitherBoth::Right(RightElem)),
-        None,
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        Some(EitherBoth::Left(LeftElem)),
-        Some(EitherBoth::Right(RightElem)),
-        None,
-        Some(EitherBoth::Left(LeftElem)),
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        Some(EitherBoth::Right(RightElem)),
-        None,
-        Some(EitherBoth::Left(LeftElem)),
-        None,
-        Some(EitherBoth::Right(RightElem)),
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        Some(EitherBoth::Left(LeftElem)),
-        None,
-        Some(EitherBoth::Right(RightElem)),
-        Some(EitherBoth::Left(LeftElem)),
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        None,
-        Some(EitherBoth::Right(RightElem)),
-        Some(EitherBoth::Left(LeftElem)),
-        None,
-        Some(EitherBoth::Both(LeftElem, RightElem)),
-        Some(EitherBoth::Right(RightElem)),
-        Some(EitherBoth::Left(LeftElem)),
-        None,
-    ];
-    let mut left_count = 0;
-    let mut right_count = 0;
-    let mut both_count = 0;
-
-    for &p in SUPERPERM {
-        match p {
-            Some(EitherBoth::Both(l, r)) => {
-                world.spawn().insert(l).insert(r);
-                both_count += 1;
-            },
-            Some(EitherBoth::Left(l)) => {
-                world.spawn().insert(l);
-                left_count += 1;
-            },
-            Some(EitherBoth::Right(r)) => {
-                world.spawn().insert(r);
-                right_count += 1;
-            },
-            None => {
-                world.spawn();
-            },
-        }
-    }
+mod common;
 
-    (left_count, right_count, both_count)
-}
+use bevy::prelude::*;
+use common::*;
 
 #[derive(Debug, PartialEq, Eq)]
 struct LeftCount(u32);
@@ -78,17 +10,18 @@ struct LeftCount(u32);
 struct RightCount(u32);
 
 bevy_either::either_many!(
-    readonly MyEither<'a, 'b>,
-    Left(&'a LeftElem),
-    Right(&'b RightElem),
+    readonly MyEither<'w>,
+    Left(&'w LeftElem),
+    Right(&'w RightElem),
 );
 
 #[test]
 fn main() {
-    let mut world = World::default();
+    let random = RandomWorld::new(0);
+    let (real_left_count, real_right_count, real_both_count) = random.counts();
+    let mut world = random.world;
     world.insert_resource(LeftCount(0));
     world.insert_resource(RightCount(0));
-    let (real_left_count, real_right_count, real_both_count) = push_entities(&mut world);
     let mut update_stage = SystemStage::single((|
             q: Query<MyEither>,
             mut l: ResMut<LeftCount>,
@@ -109,4 +42,344 @@ fn main() {
     update_stage.run(&mut world);
     assert_eq!(world.get_resource::<LeftCount>().unwrap().0, real_left_count + real_both_count);
     assert_eq!(world.get_resource::<RightCount>().unwrap().0, real_right_count);
-}
\ No newline at end of file
+}
+
+#[test]
+fn mixed_storage() {
+    use bevy::ecs::component::{ComponentDescriptor, StorageType};
+
+    let mut world = World::default();
+    world.register_component(ComponentDescriptor::new::<RightElem>(StorageType::SparseSet))
+        .unwrap();
+    world.spawn().insert(LeftElem(0));
+    world.spawn().insert(RightElem(0));
+    world.spawn().insert(LeftElem(0)).insert(RightElem(0));
+    let mut query = world.query::<MyEither>();
+    let (mut left_count, mut right_count) = (0, 0);
+    query.for_each(&world, |item| match item {
+        MyEither::Left(_) => left_count += 1,
+        MyEither::Right(_) => right_count += 1,
+    });
+    assert_eq!((left_count, right_count), (2, 1));
+}
+
+bevy_either::either_many!(
+    Shadowed<'w>,
+    Left(&'w LeftElem),
+    LeftRight((&'w LeftElem, &'w RightElem)),
+    Right(&'w RightElem),
+);
+
+#[test]
+fn archetype_branches() {
+    use bevy::ecs::query::{FetchState, WorldQuery};
+
+    let mut world = World::default();
+    world.spawn().insert(LeftElem(0));
+    world.spawn().insert(RightElem(0));
+    world.spawn().insert(LeftElem(0)).insert(RightElem(0));
+    world.spawn();
+    let state = <Shadowed as WorldQuery>::State::init(&mut world);
+    let mut kinds: Vec<_> = state.archetype_branches(world.archetypes())
+        .filter_map(|branch| branch.kind)
+        .collect();
+    kinds.sort();
+    assert_eq!(kinds, [ShadowedKind::Left, ShadowedKind::Left, ShadowedKind::Right]);
+}
+
+#[test]
+fn shadowing() {
+    assert!(bevy_either::shadowed_variants::<MyEither>().is_empty());
+    assert_eq!(bevy_either::shadowed_variants::<Shadowed>(), ["LeftRight"]);
+}
+
+#[test]
+fn configured() {
+    use bevy_either::{Configured, VariantConfig, WithEnabledVariant};
+
+    type Config = VariantConfig<Shadowed<'static>>;
+
+    let random = RandomWorld::new(0);
+    let (real_left_count, real_right_count, real_both_count) = random.counts();
+    let mut world = random.world;
+    world.insert_resource(Config::new(vec![2, 1]));
+    let mut query = world.query_filtered::<
+        Configured<Shadowed<'static>>,
+        WithEnabledVariant<Shadowed<'static>>,
+    >();
+    let (mut left_count, mut right_count) = (0, 0);
+    for item in query.iter_mut(&mut world) {
+        match item {
+            Shadowed::Right(_) => right_count += 1,
+            _ => left_count += 1,
+        }
+    }
+    assert_eq!(left_count, 0);
+    assert_eq!(right_count, real_right_count + real_both_count);
+
+    // the configuration is copied when the query is created
+    world.get_resource_mut::<Config>().unwrap().enable(0);
+    let mut query = world.query_filtered::<
+        Configured<Shadowed<'static>>,
+        WithEnabledVariant<Shadowed<'static>>,
+    >();
+    let mut left_count = 0;
+    for item in query.iter_mut(&mut world) {
+        match item {
+            Shadowed::Right(_) => {},
+            Shadowed::Left(_) => left_count += 1,
+            Shadowed::LeftRight(_) => unreachable!(),
+        }
+    }
+    assert_eq!(left_count, real_left_count);
+}
+
+bevy_either::either_many!(
+    Conflicting<'w>,
+    Both((&'w LeftElem, &'w RightElem)),
+    MutLeft(&'w mut LeftElem),
+    MutRight(&'w mut RightElem),
+);
+
+#[test]
+fn exclusive_access() {
+    let random = RandomWorld::new(0);
+    let (real_left_count, real_right_count, real_both_count) = random.counts();
+    let mut world = random.world;
+    let mut query = world.query::<Conflicting>();
+    let (mut left_count, mut right_count, mut both_count) = (0, 0, 0);
+    for item in query.iter_mut(&mut world) {
+        match item {
+            Conflicting::Both(_) => both_count += 1,
+            Conflicting::MutLeft(_) => left_count += 1,
+            Conflicting::MutRight(_) => right_count += 1,
+        }
+    }
+    assert_eq!(left_count, real_left_count);
+    assert_eq!(right_count, real_right_count);
+    assert_eq!(both_count, real_both_count);
+}
+
+bevy_either::either_many!(
+    FilteredBranch<'w>,
+    WithRight((&'w mut LeftElem, With<RightElem>)),
+    Plain(&'w mut LeftElem),
+);
+
+#[test]
+#[should_panic]
+fn branch_filters_across_params() {
+    let mut world = World::default();
+    let mut stage = SystemStage::single((|
+        _: Query<FilteredBranch>,
+        _: Query<&mut LeftElem, Without<RightElem>>,
+    | {}).system());
+    stage.run(&mut world);
+}
+
+bevy_either::either_many!(
+    readonly Borrowless,
+    HasLeft(With<LeftElem>),
+    Other(Entity),
+);
+
+#[test]
+fn borrowless() {
+    let random = RandomWorld::new(0);
+    let (real_left_count, _, real_both_count) = random.counts();
+    let mut world = random.world;
+    let mut query = world.query::<Borrowless>();
+    let (mut left_count, mut other_count) = (0, 0);
+    for item in query.iter(&world) {
+        match item {
+            Borrowless::HasLeft(_) => left_count += 1,
+            Borrowless::Other(_) => other_count += 1,
+        }
+    }
+    assert_eq!(left_count, real_left_count + real_both_count);
+    assert_eq!(left_count + other_count, world.entities().len());
+}
+
+#[test]
+fn get_variant() {
+    use bevy_either::EitherQueryError;
+
+    let mut world = World::default();
+    let left = world.spawn().insert(LeftElem(0)).id();
+    let right = world.spawn().insert(RightElem(0)).id();
+    let none = world.spawn().id();
+    let mut read_stage = SystemStage::single((move |
+        either: Query<MyEither>,
+        borrowless: Query<Borrowless>,
+    | {
+        assert!(either.get_left(left).is_ok());
+        assert!(either.get_right(right).is_ok());
+        assert_eq!(either.get_left(right).err(), Some(EitherQueryError::OtherVariant("Right")));
+        assert_eq!(either.get_right(none).err(), Some(EitherQueryError::QueryDoesNotMatch));
+        assert_eq!(borrowless.get_has_left(left), Ok(true));
+        assert_eq!(borrowless.get_other(right), Ok(right));
+        assert_eq!(borrowless.get_other(left), Err(EitherQueryError::OtherVariant("HasLeft")));
+    }).system());
+    read_stage.run(&mut world);
+    let mut write_stage = SystemStage::single((move |mut conflicting: Query<Conflicting>| {
+        assert!(conflicting.get_mut_left_mut(left).is_ok());
+        assert_eq!(
+            conflicting.get_both_mut(right).err(),
+            Some(EitherQueryError::OtherVariant("MutRight")),
+        );
+    }).system());
+    write_stage.run(&mut world);
+}
+
+#[test]
+fn run_criteria() {
+    use bevy_either::run_if_any_variant;
+
+    #[derive(Default)]
+    struct Runs(u32);
+
+    let mut world = World::default();
+    world.insert_resource(Runs::default());
+    let mut stage = SystemStage::single((|mut runs: ResMut<Runs>, query: Query<Borrowless>| {
+        assert!(query.iter().any(|item| item.kind() == BorrowlessKind::HasLeft));
+        runs.0 += 1;
+    }).system().with_run_criteria(run_if_any_variant::<Borrowless>(BorrowlessKind::HasLeft)));
+    world.spawn().insert(RightElem(0));
+    stage.run(&mut world);
+    world.spawn().insert(LeftElem(0)).insert(RightElem(0));
+    stage.run(&mut world);
+    assert_eq!(world.get_resource::<Runs>().unwrap().0, 1);
+}
+
+#[test]
+fn accessors() {
+    let random = RandomWorld::new(0);
+    let (real_left_count, real_right_count, real_both_count) = random.counts();
+    let mut world = random.world;
+    let mut query = world.query::<Conflicting>();
+    let (mut left_count, mut right_count, mut both_count) = (0, 0, 0);
+    for mut item in query.iter_mut(&mut world) {
+        if item.is_both() {
+            assert!(item.as_both().is_some());
+            assert!(item.as_mut_left().is_none());
+            both_count += 1;
+        } else if item.as_mut_right_mut().is_some() {
+            right_count += 1;
+        } else {
+            item = item.map_mut_left(|left| left);
+            assert!(item.into_mut_left().is_ok());
+            left_count += 1;
+        }
+    }
+    assert_eq!(left_count, real_left_count);
+    assert_eq!(right_count, real_right_count);
+    assert_eq!(both_count, real_both_count);
+}
+
+bevy_either::either_many!(
+    readonly Shared<'w>,
+    shared(Entity),
+    Left(&'w LeftElem),
+    Right(&'w RightElem),
+);
+
+#[test]
+fn shared() {
+    let random = RandomWorld::new(0);
+    let (real_left_count, real_right_count, real_both_count) = random.counts();
+    let mut world = random.world;
+    let mut query = world.query::<Shared>();
+    let (mut left_count, mut right_count) = (0, 0);
+    for (entity, variant) in query.iter(&world) {
+        assert_eq!(world.get::<LeftElem>(entity).is_some(), variant.is_left());
+        match variant {
+            SharedVariant::Left(_) => left_count += 1,
+            SharedVariant::Right(_) => right_count += 1,
+        }
+    }
+    assert_eq!(left_count, real_left_count + real_both_count);
+    assert_eq!(right_count, real_right_count);
+}
+
+pub trait Side {
+    fn is_left(&self) -> bool;
+}
+
+impl Side for LeftElem {
+    fn is_left(&self) -> bool {
+        true
+    }
+}
+
+impl Side for RightElem {
+    fn is_left(&self) -> bool {
+        false
+    }
+}
+
+bevy_either::either_many!(
+    Sides<'w> as mut dyn Side,
+    shared(Entity),
+    Left(&'w mut LeftElem),
+    Right(&'w mut RightElem),
+);
+
+#[test]
+fn trait_objects() {
+    let mut world = RandomWorld::new(0).world;
+    let mut query = world.query::<Sides>();
+    for (_, mut variant) in query.iter_mut(&mut world) {
+        assert_eq!(variant.as_dyn().is_left(), variant.is_left());
+        assert_eq!(variant.as_dyn_mut().is_left(), variant.is_left());
+    }
+}
+
+bevy_either::either_many!(
+    readonly Tagged,
+    shared(Entity),
+    HasLeft(With<LeftElem>),
+    Other(With<RightElem>),
+);
+
+#[test]
+fn shared_get_variant() {
+    use bevy_either::EitherQueryError;
+
+    let mut world = World::default();
+    let left = world.spawn().insert(LeftElem(1)).id();
+    let right = world.spawn().insert(RightElem(2)).id();
+    let mut read_stage = SystemStage::single((move |
+        shared: Query<Shared>,
+        tagged: Query<Tagged>,
+    | {
+        assert_eq!(shared.get_left(left), Ok((left, &LeftElem(1))));
+        assert_eq!(shared.get_right(right), Ok((right, &RightElem(2))));
+        assert_eq!(shared.get_right(left).err(), Some(EitherQueryError::OtherVariant("Left")));
+        assert_eq!(tagged.get_has_left(left), Ok((left, true)));
+        assert_eq!(tagged.get_other(left), Err(EitherQueryError::OtherVariant("HasLeft")));
+    }).system());
+    read_stage.run(&mut world);
+    let mut write_stage = SystemStage::single((move |mut sides: Query<Sides>| {
+        let (entity, mut item) = sides.get_left_mut(left).unwrap();
+        assert_eq!(entity, left);
+        item.0 += 10;
+        assert!(sides.get_left_mut(right).is_err());
+    }).system());
+    write_stage.run(&mut world);
+    assert_eq!(world.get::<LeftElem>(left), Some(&LeftElem(11)));
+}
+
+bevy_either::either_many!(
+    readonly ReadSides<'w> as dyn Side,
+    Left(&'w LeftElem),
+    Right(&'w RightElem),
+);
+
+#[test]
+fn readonly_trait_objects() {
+    let mut world = RandomWorld::new(0).world;
+    let mut query = world.query::<ReadSides>();
+    for variant in query.iter(&world) {
+        assert_eq!(variant.as_dyn().is_left(), variant.is_left());
+    }
+}
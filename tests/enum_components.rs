@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+use bevy_either::{EnumComponents, InsertEither};
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Target(u32);
+
+#[derive(EnumComponents)]
+pub enum Ai {
+    Idle,
+    Chase(Target, u32),
+    Flee { speed: u32 },
+}
+
+#[test]
+fn insert_and_query() {
+    let mut world = World::default();
+    let entity = world.spawn().insert_enum(Ai::Chase(Target(1), 2)).id();
+    world.spawn().insert_enum(Ai::Idle);
+    assert_eq!(world.get::<AiChase>(entity).map(|chase| chase.1), Some(2));
+
+    world.entity_mut(entity).insert_enum(Ai::Flee { speed: 3 });
+    assert!(world.get::<AiChase>(entity).is_none());
+    assert_eq!(world.get::<AiFlee>(entity).map(|flee| flee.speed), Some(3));
+
+    let mut query = world.query::<(Entity, AiMut)>();
+    for (e, ai) in query.iter_mut(&mut world) {
+        match ai {
+            AiMut::Idle(_) => assert_ne!(e, entity),
+            AiMut::Chase(_) => unreachable!(),
+            AiMut::Flee(mut flee) => flee.speed += 1,
+        }
+    }
+    let mut query = world.query::<AiRef>();
+    let speeds = query
+        .iter(&world)
+        .filter_map(|ai| ai.into_flee().ok().map(|flee| flee.speed))
+        .collect::<Vec<_>>();
+    assert_eq!(speeds, [4]);
+}
+
+#[test]
+fn insert_with_commands() {
+    let mut world = World::default();
+    let entity = world.spawn().insert(AiIdle).id();
+    let mut stage = SystemStage::single((move |mut commands: Commands| {
+        commands.entity(entity).insert_enum(Ai::Chase(Target(5), 6));
+    }).system());
+    stage.run(&mut world);
+    assert!(world.get::<AiIdle>(entity).is_none());
+    assert_eq!(world.get::<AiChase>(entity).map(|chase| &chase.0), Some(&Target(5)));
+}
+
+#[test]
+fn insert_moves_once() {
+    let mut world = World::default();
+    let entity = world.spawn().insert_bundle((AiIdle, AiFlee { speed: 1 })).id();
+    let archetype_count = world.archetypes().len();
+    // removing the other variants one at a time would also create an archetype with only `AiFlee`
+    world.entity_mut(entity).insert_enum(Ai::Chase(Target(2), 3));
+    assert_eq!(world.archetypes().len(), archetype_count + 1);
+    assert!(world.get::<AiIdle>(entity).is_none());
+    assert!(world.get::<AiFlee>(entity).is_none());
+}
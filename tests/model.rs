@@ -0,0 +1,361 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_either::*;
+use common::*;
+use core::any::TypeId;
+
+#[test]
+fn either() {
+    check_worlds(
+        |world| world.query::<(Entity, Either<&LeftElem, &RightElem>)>(),
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| entry.left.map(Either::Left).or_else(|| entry.right.map(Either::Right)),
+            |item| match item {
+                Either::Left(&left) => Either::Left(left),
+                Either::Right(&right) => Either::Right(right),
+            },
+        ),
+    );
+}
+
+#[test]
+fn either_both() {
+    check_worlds(
+        |world| world.query::<(Entity, EitherBoth<&LeftElem, &RightElem>)>(),
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| match (entry.left, entry.right) {
+                (Some(left), None) => Some(EitherBoth::Left(left)),
+                (None, Some(right)) => Some(EitherBoth::Right(right)),
+                (Some(left), Some(right)) => Some(EitherBoth::Both(left, right)),
+                (None, None) => None,
+            },
+            |item| match item {
+                EitherBoth::Left(&left) => EitherBoth::Left(left),
+                EitherBoth::Right(&right) => EitherBoth::Right(right),
+                EitherBoth::Both(&left, &right) => EitherBoth::Both(left, right),
+            },
+        ),
+    );
+}
+
+#[derive(Debug, PartialEq)]
+enum Owned {
+    Both(LeftElem, RightElem),
+    Left(LeftElem),
+    Right(RightElem),
+    OnlyExtra(Extra),
+}
+
+bevy_either::either_many!(
+    readonly Many<'w>,
+    Both((&'w LeftElem, &'w RightElem)),
+    Left(&'w LeftElem),
+    Right(&'w RightElem),
+    OnlyExtra(&'w Extra),
+);
+
+#[test]
+fn either_many() {
+    check_worlds(
+        |world| world.query::<(Entity, Many)>(),
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| match *entry {
+                Entry { left: Some(left), right: Some(right), .. } => {
+                    Some(Owned::Both(left, right))
+                },
+                Entry { left: Some(left), .. } => Some(Owned::Left(left)),
+                Entry { right: Some(right), .. } => Some(Owned::Right(right)),
+                Entry { extra, .. } => extra.map(Owned::OnlyExtra),
+            },
+            |item| match item {
+                Many::Both((&left, &right)) => Owned::Both(left, right),
+                Many::Left(&left) => Owned::Left(left),
+                Many::Right(&right) => Owned::Right(right),
+                Many::OnlyExtra(&extra) => Owned::OnlyExtra(extra),
+            },
+        ),
+    );
+}
+
+/// The components fetched by each branch used by the [`Either3`] to [`Either8`] models, as whether
+/// [`LeftElem`], [`RightElem`] and [`Extra`] are fetched.
+const BRANCHES: [(bool, bool, bool); 8] = [
+    (true, true, true),
+    (true, true, false),
+    (true, false, true),
+    (false, true, true),
+    (true, false, false),
+    (false, true, false),
+    (false, false, true),
+    (false, false, false),
+];
+
+/// Returns whether the branch `index` of [`BRANCHES`] matches `entry`, along with the part of the
+/// entry it fetches.
+fn branch(entry: &Entry, index: usize) -> Option<Entry> {
+    let (left, right, extra) = BRANCHES[index];
+    let matches = (!left || entry.left.is_some())
+        && (!right || entry.right.is_some())
+        && (!extra || entry.extra.is_some());
+    matches.then(|| Entry {
+        left: entry.left.filter(|_| left),
+        right: entry.right.filter(|_| right),
+        extra: entry.extra.filter(|_| extra),
+    })
+}
+
+/// Returns the first of `branches` matching `entry`, along with the part of the entry it fetches.
+fn first_branch(entry: &Entry, branches: &[usize]) -> Option<(usize, Entry)> {
+    branches.iter().find_map(|&index| branch(entry, index).map(|fetched| (index, fetched)))
+}
+
+/// Copies a branch's item out of the world.
+trait ToEntry {
+    fn to_entry(self) -> Entry;
+}
+
+impl ToEntry for (&LeftElem, &RightElem, &Extra) {
+    fn to_entry(self) -> Entry {
+        Entry { left: Some(*self.0), right: Some(*self.1), extra: Some(*self.2) }
+    }
+}
+
+impl ToEntry for (&LeftElem, &RightElem) {
+    fn to_entry(self) -> Entry {
+        Entry { left: Some(*self.0), right: Some(*self.1), extra: None }
+    }
+}
+
+impl ToEntry for (&LeftElem, &Extra) {
+    fn to_entry(self) -> Entry {
+        Entry { left: Some(*self.0), right: None, extra: Some(*self.1) }
+    }
+}
+
+impl ToEntry for (&RightElem, &Extra) {
+    fn to_entry(self) -> Entry {
+        Entry { left: None, right: Some(*self.0), extra: Some(*self.1) }
+    }
+}
+
+impl ToEntry for &LeftElem {
+    fn to_entry(self) -> Entry {
+        Entry { left: Some(*self), ..Entry::default() }
+    }
+}
+
+impl ToEntry for &RightElem {
+    fn to_entry(self) -> Entry {
+        Entry { right: Some(*self), ..Entry::default() }
+    }
+}
+
+impl ToEntry for &Extra {
+    fn to_entry(self) -> Entry {
+        Entry { extra: Some(*self), ..Entry::default() }
+    }
+}
+
+impl ToEntry for Entity {
+    fn to_entry(self) -> Entry {
+        Entry::default()
+    }
+}
+
+macro_rules! either_n_model {
+    ($test:ident $either:ident, $($var:ident $index:literal $query:ty),+) => {
+        #[test]
+        fn $test() {
+            check_worlds(
+                |world| world.query::<(Entity, $either<$($query),+>)>(),
+                |random, state| assert_model(
+                    random,
+                    state,
+                    |entry| first_branch(entry, &[$($index),+]),
+                    |item| match item {
+                        $($either::$var(item) => ($index, item.to_entry()),)+
+                    },
+                ),
+            );
+        }
+    };
+}
+
+either_n_model!(either3 Either3,
+    A 0 (&LeftElem, &RightElem, &Extra),
+    B 1 (&LeftElem, &RightElem),
+    C 2 (&LeftElem, &Extra)
+);
+
+either_n_model!(either4 Either4,
+    A 4 &LeftElem,
+    B 5 &RightElem,
+    C 6 &Extra,
+    D 7 Entity
+);
+
+either_n_model!(either5 Either5,
+    A 3 (&RightElem, &Extra),
+    B 2 (&LeftElem, &Extra),
+    C 1 (&LeftElem, &RightElem),
+    D 4 &LeftElem,
+    E 6 &Extra
+);
+
+either_n_model!(either6 Either6,
+    A 1 (&LeftElem, &RightElem),
+    B 0 (&LeftElem, &RightElem, &Extra),
+    C 5 &RightElem,
+    D 3 (&RightElem, &Extra),
+    E 4 &LeftElem,
+    F 6 &Extra
+);
+
+either_n_model!(either7 Either7,
+    A 6 &Extra,
+    B 5 &RightElem,
+    C 4 &LeftElem,
+    D 3 (&RightElem, &Extra),
+    E 2 (&LeftElem, &Extra),
+    F 1 (&LeftElem, &RightElem),
+    G 0 (&LeftElem, &RightElem, &Extra)
+);
+
+either_n_model!(either8 Either8,
+    A 0 (&LeftElem, &RightElem, &Extra),
+    B 1 (&LeftElem, &RightElem),
+    C 2 (&LeftElem, &Extra),
+    D 3 (&RightElem, &Extra),
+    E 4 &LeftElem,
+    F 5 &RightElem,
+    G 6 &Extra,
+    H 7 Entity
+);
+
+#[test]
+fn at_least() {
+    check_worlds(
+        |world| world.query::<(Entity, AtLeast<2, (&LeftElem, &RightElem, &Extra)>)>(),
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| {
+                let count = entry.left.is_some() as usize
+                    + entry.right.is_some() as usize
+                    + entry.extra.is_some() as usize;
+                (count >= 2).then_some(*entry)
+            },
+            |(left, right, extra)| Entry {
+                left: left.copied(),
+                right: right.copied(),
+                extra: extra.copied(),
+            },
+        ),
+    );
+}
+
+#[test]
+fn not_query() {
+    check_worlds(
+        |world| world.query::<(Entity, NotQuery<Either<&LeftElem, &RightElem>>)>(),
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| (entry.left.is_none() && entry.right.is_none()).then_some(true),
+            |item| item,
+        ),
+    );
+}
+
+type Configurable = Either3<&'static LeftElem, &'static RightElem, &'static Extra>;
+
+#[test]
+fn configured() {
+    check_worlds(
+        |world| {
+            world.insert_resource(VariantConfig::<Configurable>::new(vec![2, 0]));
+            world.query::<(
+                Entity,
+                (Configured<Configurable>, WithEnabledVariant<Configurable>),
+            )>()
+        },
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| {
+                // `B` is disabled, so it's only given when no enabled variant matches
+                let fetched = |index| match index {
+                    0 => entry.left.map(|left| Entry { left: Some(left), ..Entry::default() }),
+                    1 => entry.right.map(|right| Entry { right: Some(right), ..Entry::default() }),
+                    _ => entry.extra.map(|extra| Entry { extra: Some(extra), ..Entry::default() }),
+                };
+                [(2, true), (0, true), (1, false)]
+                    .iter()
+                    .find_map(|&(index, enabled)| fetched(index).map(|f| (index, f, enabled)))
+            },
+            |(item, enabled)| match item {
+                Either3::A(left) => (0, left.to_entry(), enabled),
+                Either3::B(right) => (1, right.to_entry(), enabled),
+                Either3::C(extra) => (2, extra.to_entry(), enabled),
+            },
+        ),
+    );
+}
+
+struct Dynamic;
+
+#[test]
+fn dynamic_either() {
+    check_worlds(
+        |world| {
+            let components = world.components();
+            let left = components.get_id(TypeId::of::<LeftElem>()).unwrap();
+            let right = components.get_id(TypeId::of::<RightElem>()).unwrap();
+            let extra = components.get_id(TypeId::of::<Extra>()).unwrap();
+            world.insert_resource(DynamicEitherBranches::<Dynamic>::new(vec![
+                DynamicBranch::new(vec![left, right], vec![]),
+                DynamicBranch::new(vec![extra], vec![right]),
+                DynamicBranch::new(vec![right], vec![]),
+            ]));
+            world.query::<(Entity, DynamicEither<Dynamic>)>()
+        },
+        |random, state| assert_model(
+            random,
+            state,
+            |entry| match *entry {
+                Entry { left: Some(left), right: Some(right), .. } => {
+                    Some((0, Entry { left: Some(left), right: Some(right), extra: None }))
+                },
+                Entry { right: None, extra: Some(extra), .. } => {
+                    Some((1, Entry { extra: Some(extra), ..Entry::default() }))
+                },
+                Entry { right: Some(right), .. } => {
+                    Some((2, Entry { right: Some(right), ..Entry::default() }))
+                },
+                _ => None,
+            },
+            |item| {
+                let components = &item.components;
+                // SAFE: the pointers are to the branch's required components, in order
+                let entry = unsafe {
+                    match item.branch {
+                        0 => (
+                            &*components[0].cast::<LeftElem>().as_ptr(),
+                            &*components[1].cast::<RightElem>().as_ptr(),
+                        ).to_entry(),
+                        1 => (&*components[0].cast::<Extra>().as_ptr()).to_entry(),
+                        _ => (&*components[0].cast::<RightElem>().as_ptr()).to_entry(),
+                    }
+                };
+                (item.branch, entry)
+            },
+        ),
+    );
+}